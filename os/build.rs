@@ -1,12 +1,65 @@
-use std::fs::{read_dir, File};
+use std::env;
+use std::fs::{read_dir, read_to_string, File};
 use std::io::{Result, Write};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() {
     println!("cargo:rerun-if-changed=../user/src/");
     println!("cargo:rerun-if-changed={}", TARGET_PATH);
     insert_app_data().unwrap();
+    insert_ksymbols().unwrap();
+    emit_build_info();
 }
 
+/// run `git` with `args` in the repository, `None` if it fails
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Pass the build information reported by `sys_uname` to the kernel as
+/// environment variables.
+///
+/// Building outside of a git checkout, or without git installed, gives a
+/// build id of `unknown`.
+fn emit_build_info() {
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/index", git_dir);
+    }
+    let build_id = match git(&["rev-parse", "--short=12", "HEAD"]) {
+        Some(hash) => match git(&["status", "--porcelain", "--untracked-files=no"]) {
+            Some(status) if !status.is_empty() => format!("{}-dirty", hash),
+            _ => hash,
+        },
+        None => "unknown".to_string(),
+    };
+    // reproducible builds pin the time with SOURCE_DATE_EPOCH
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    let build_time = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|time| time.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs())
+        });
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=KERNEL_BUILD_ID={}", build_id);
+    println!("cargo:rustc-env=KERNEL_BUILD_TIME={}", build_time);
+    println!("cargo:rustc-env=KERNEL_FEATURES={}", features.join(","));
+}
+
+/// `nm` output of the previous kernel build, written by the Makefile
+static KSYMBOLS_PATH: &str = "target/ksymbols.txt";
+
 static TARGET_PATH: &str = "../user/target/riscv64gc-unknown-none-elf/release/";
 
 fn insert_app_data() -> Result<()> {
@@ -55,3 +108,67 @@ app_{0}_end:"#,
     }
     Ok(())
 }
+
+/// Generate `src/ksymbols.S`, a table of the kernel text symbols sorted by
+/// address, from the `nm` output of the previous build.
+///
+/// The table is emitted into `.rodata`, after all of `.text`, so embedding it
+/// does not move the functions it describes.
+fn insert_ksymbols() -> Result<()> {
+    let mut symbols: Vec<(u64, String)> = Vec::new();
+    if env::var_os("CARGO_FEATURE_KSYMBOLS").is_some() {
+        println!("cargo:rerun-if-changed={}", KSYMBOLS_PATH);
+        if let Ok(nm) = read_to_string(KSYMBOLS_PATH) {
+            for line in nm.lines() {
+                // "<addr> <type> <name>", keep text symbols only
+                let mut fields = line.splitn(3, ' ');
+                let (addr, kind, name) = match (fields.next(), fields.next(), fields.next()) {
+                    (Some(addr), Some(kind), Some(name)) => (addr, kind, name),
+                    _ => continue,
+                };
+                if kind != "t" && kind != "T" {
+                    continue;
+                }
+                if let Ok(addr) = u64::from_str_radix(addr, 16) {
+                    symbols.push((addr, strip_hash(name).to_string()));
+                }
+            }
+        }
+    }
+    symbols.sort();
+    symbols.dedup_by_key(|(addr, _)| *addr);
+
+    let mut f = File::create("src/ksymbols.S").unwrap();
+    writeln!(
+        f,
+        r#"
+    .section .rodata.ksymbols
+    .align 3
+    .global _num_ksymbols
+_num_ksymbols:
+    .quad {}
+    .global _ksymbols
+_ksymbols:"#,
+        symbols.len()
+    )?;
+    for (idx, (addr, name)) in symbols.iter().enumerate() {
+        writeln!(f, "    .quad {:#x}, ksym_{}, {}", addr, idx, name.len())?;
+    }
+    for (idx, (_, name)) in symbols.iter().enumerate() {
+        let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+        writeln!(f, "ksym_{}:\n    .ascii \"{}\"", idx, escaped)?;
+    }
+    Ok(())
+}
+
+/// strip the `::h0123456789abcdef` suffix of demangled rust symbols
+fn strip_hash(name: &str) -> &str {
+    match name.rfind("::h") {
+        Some(pos)
+            if name.len() - pos == 19 && name[pos + 3..].chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            &name[..pos]
+        }
+        _ => name,
+    }
+}
@@ -0,0 +1,193 @@
+//! Kernel backtraces
+//!
+//! The kernel is built with `-Cforce-frame-pointers=yes`, so every frame
+//! saves `ra` at `fp - 8` and the caller's `fp` at `fp - 16`. With the
+//! `ksymbols` feature, return addresses are resolved to `function+offset`
+//! using a symbol table generated from `nm` output and embedded by `build.rs`.
+
+use crate::config::{kernel_stack_position, KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE};
+use core::arch::asm;
+use core::fmt;
+
+#[cfg(feature = "ksymbols")]
+core::arch::global_asm!(include_str!("ksymbols.S"));
+
+/// maximum number of frames printed
+const MAX_DEPTH: usize = 32;
+
+#[cfg(feature = "ksymbols")]
+#[repr(C)]
+/// an entry of the embedded symbol table
+struct KernelSymbol {
+    addr: usize,
+    name: *const u8,
+    len: usize,
+}
+
+#[cfg(feature = "ksymbols")]
+/// find the function containing `addr`, returns its name and the offset of
+/// `addr` into it
+pub fn lookup(addr: usize) -> Option<(&'static str, usize)> {
+    extern "C" {
+        fn _num_ksymbols();
+        fn _ksymbols();
+        fn etext();
+    }
+    if addr >= etext as usize {
+        return None;
+    }
+    let symbols = unsafe {
+        core::slice::from_raw_parts(
+            _ksymbols as usize as *const KernelSymbol,
+            (_num_ksymbols as usize as *const usize).read_volatile(),
+        )
+    };
+    let idx = match symbols.binary_search_by_key(&addr, |symbol| symbol.addr) {
+        Ok(idx) => idx,
+        Err(0) => return None,
+        Err(idx) => idx - 1,
+    };
+    let symbol = &symbols[idx];
+    let name = unsafe {
+        core::str::from_utf8_unchecked(core::slice::from_raw_parts(symbol.name, symbol.len))
+    };
+    Some((name, addr - symbol.addr))
+}
+
+#[cfg(not(feature = "ksymbols"))]
+/// without the symbol table no address can be resolved
+pub fn lookup(_addr: usize) -> Option<(&'static str, usize)> {
+    None
+}
+
+/// an address printed as `0x... <function+offset>` when it can be resolved
+pub struct Symbol(pub usize);
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)?;
+        if let Some((name, offset)) = lookup(self.0) {
+            write!(f, " <{}+{:#x}>", name, offset)?;
+        }
+        Ok(())
+    }
+}
+
+/// the kernel stack containing `sp`, so that the frame walk never leaves it
+fn stack_bounds(sp: usize) -> Option<(usize, usize)> {
+    extern "C" {
+        fn boot_stack();
+        fn boot_stack_top();
+    }
+    if (boot_stack as usize..boot_stack_top as usize).contains(&sp) {
+        return Some((boot_stack as usize, boot_stack_top as usize));
+    }
+    if sp < TRAMPOLINE {
+        let app_id = (TRAMPOLINE - sp) / (KERNEL_STACK_SIZE + PAGE_SIZE);
+        let (bottom, top) = kernel_stack_position(app_id);
+        if (bottom..top).contains(&sp) {
+            return Some((bottom, top));
+        }
+    }
+    None
+}
+
+/// Walk the frame pointer chain from `fp`, calling `f` with each return
+/// address until it returns false.
+///
+/// The walk never leaves the kernel stack containing `sp`, so it stops at
+/// the user `s0` saved by `__alltraps` instead of following it.
+fn walk(mut fp: usize, sp: usize, mut f: impl FnMut(usize) -> bool) {
+    extern "C" {
+        fn stext();
+        fn etext();
+    }
+    let (bottom, top) = match stack_bounds(sp) {
+        Some(bounds) => bounds,
+        None => return,
+    };
+    for _ in 0..MAX_DEPTH {
+        if fp % 8 != 0 || fp < bottom + 16 || fp > top {
+            break;
+        }
+        let (ra, next_fp) = unsafe { (*((fp - 8) as *const usize), *((fp - 16) as *const usize)) };
+        if !(stext as usize..etext as usize).contains(&ra) || !f(ra) {
+            break;
+        }
+        if next_fp <= fp {
+            break;
+        }
+        fp = next_fp;
+    }
+}
+
+#[cfg(feature = "frame_debug")]
+#[inline(never)]
+/// Fill `trace` with the return addresses on the current kernel stack,
+/// innermost first, and return how many were found.
+pub fn collect(trace: &mut [usize]) -> usize {
+    let (fp, sp): (usize, usize);
+    unsafe {
+        asm!("mv {}, s0", "mv {}, sp", out(reg) fp, out(reg) sp);
+    }
+    let mut depth = 0;
+    walk(fp, sp, |ra| {
+        if depth == trace.len() {
+            return false;
+        }
+        trace[depth] = ra;
+        depth += 1;
+        true
+    });
+    depth
+}
+
+#[inline(never)]
+/// print the return addresses on the current kernel stack, innermost first
+pub fn kernel_backtrace() {
+    let (fp, sp): (usize, usize);
+    unsafe {
+        asm!("mv {}, s0", "mv {}, sp", out(reg) fp, out(reg) sp);
+    }
+    if stack_bounds(sp).is_none() {
+        return;
+    }
+    println!("Backtrace:");
+    let mut depth = 0;
+    walk(fp, sp, |ra| {
+        // `ra` points after the call, which may already be the next function
+        // if the callee does not return
+        match lookup(ra - 1) {
+            Some((name, offset)) => println!("  #{} {:#x} <{}+{:#x}>", depth, ra, name, offset + 1),
+            None => println!("  #{} {:#x}", depth, ra),
+        }
+        depth += 1;
+        true
+    });
+}
+
+#[cfg(feature = "backtrace_test")]
+/// panic three calls deep, the backtrace printed by the panic handler should
+/// name `backtrace_test_c`, `backtrace_test_b` and `backtrace_test_a` in order
+pub fn backtrace_test() {
+    println!("backtrace_test: panicking on purpose");
+    backtrace_test_a();
+}
+
+#[cfg(feature = "backtrace_test")]
+#[inline(never)]
+fn backtrace_test_a() -> usize {
+    backtrace_test_b() + 1
+}
+
+#[cfg(feature = "backtrace_test")]
+#[inline(never)]
+fn backtrace_test_b() -> usize {
+    backtrace_test_c() + 1
+}
+
+#[cfg(feature = "backtrace_test")]
+#[inline(never)]
+fn backtrace_test_c() -> usize {
+    panic!("controlled panic");
+}
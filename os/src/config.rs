@@ -1,8 +1,17 @@
 //! Constants used in rCore
 
 pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// the user stack may grow on page faults down to this many bytes below its
+/// initial bottom
+pub const USER_STACK_GROW_LIMIT: usize = 0x10_0000;
+/// a page fault grows the user stack only if it is at most this many pages
+/// below the lowest stack page, further ones are wild accesses
+pub const USER_STACK_GROW_WINDOW: usize = 16;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
 pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
+/// frames the kernel heap may claim from the frame allocator when the static
+/// heap is exhausted
+pub const KERNEL_HEAP_GROW_LIMIT: usize = 256;
 pub const MAX_APP_NUM: usize = 16;
 pub const APP_BASE_ADDRESS: usize = 0x80400000;
 pub const APP_SIZE_LIMIT: usize = 0x20000;
@@ -12,6 +21,12 @@ pub const PAGE_SIZE_BITS: usize = 12;
 pub const MEMORY_END: usize = 0x80800000;
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+pub use syscall_abi::{PROC_INFO, VDSO};
+const _: () = assert!(PAGE_SIZE == syscall_abi::PAGE_SIZE && VDSO == TRAP_CONTEXT - PAGE_SIZE);
+/// base address of the Goldfish RTC of qemu virt
+pub const RTC_BASE: usize = 0x0010_1000;
+/// memory-mapped device registers of qemu virt: (base, size)
+pub const MMIO: &[(usize, usize)] = &[(RTC_BASE, 0x1000)];
 
 pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
     let top = TRAMPOLINE - app_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
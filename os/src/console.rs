@@ -1,21 +1,54 @@
 use crate::sbi::console_putchar;
+use crate::sync::UPSafeCell;
 use core::fmt::{self, Write};
+use lazy_static::*;
 
 struct Stdout;
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            console_putchar(c as usize);
-        }
+        write_bytes(s.as_bytes());
         Ok(())
     }
 }
 
+/// Put raw bytes to the console.
+///
+/// User buffers need not be valid UTF-8, and a character split between two
+/// buffers is put together again by the terminal.
+pub fn write_bytes(bytes: &[u8]) {
+    for &byte in bytes {
+        console_putchar(byte as usize);
+    }
+}
+
 pub fn print(args: fmt::Arguments) {
     Stdout.write_fmt(args).unwrap();
 }
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+/// terminal window size, laid out as `struct winsize` of Linux
+pub struct WinSize {
+    pub rows: u16,
+    pub cols: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
+lazy_static! {
+    /// window size of the console; we cannot ask the host terminal, so it
+    /// stays 80x24 until set by user space
+    pub static ref CONSOLE_WINSIZE: UPSafeCell<WinSize> = unsafe {
+        UPSafeCell::new(WinSize {
+            rows: 24,
+            cols: 80,
+            xpixel: 0,
+            ypixel: 0,
+        })
+    };
+}
+
 #[macro_export]
 macro_rules! print {
     ($fmt: literal $(, $($arg: tt)+)?) => {
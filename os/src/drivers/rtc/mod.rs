@@ -0,0 +1,52 @@
+//! Real time clock drivers
+//!
+//! The only implementation is the Goldfish RTC found on qemu virt.
+
+use crate::config::RTC_BASE;
+use core::ptr::read_volatile;
+
+/// a device which knows the wall-clock time
+pub trait RtcDevice {
+    /// nanoseconds since the Unix epoch
+    fn read_time_ns(&self) -> u64;
+}
+
+/// Goldfish RTC, see `hw/rtc/goldfish_rtc.c` of qemu
+pub struct GoldfishRtc {
+    base: usize,
+}
+
+/// low 32 bits of the time, reading it latches the high 32 bits
+const TIME_LOW: usize = 0x00;
+/// high 32 bits of the time latched by reading `TIME_LOW`
+const TIME_HIGH: usize = 0x04;
+
+impl GoldfishRtc {
+    /// # Safety
+    ///
+    /// `base` must be the identically mapped base address of a Goldfish RTC.
+    pub const unsafe fn new(base: usize) -> Self {
+        Self { base }
+    }
+}
+
+impl RtcDevice for GoldfishRtc {
+    fn read_time_ns(&self) -> u64 {
+        unsafe {
+            let low = read_volatile((self.base + TIME_LOW) as *const u32) as u64;
+            let high = read_volatile((self.base + TIME_HIGH) as *const u32) as u64;
+            (high << 32) | low
+        }
+    }
+}
+
+/// probe the RTC of the board, `None` if it does not report a plausible time
+pub fn probe() -> Option<GoldfishRtc> {
+    let rtc = unsafe { GoldfishRtc::new(RTC_BASE) };
+    // a working RTC is surely past 2000-01-01
+    if rtc.read_time_ns() > 946_684_800 * 1_000_000_000 {
+        Some(rtc)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,81 @@
+//! Fault injection for exercising kernel error paths, enabled by the
+//! `fault_inject` feature.
+//!
+//! `sys_fault_inject` arms an injection site for the calling task. Each site
+//! is checked by a single branch where the resource is acquired, and only
+//! fails operations performed on behalf of a syscall of that task, so other
+//! tasks and the boot sequence are not affected.
+
+use crate::task::syscall_in_progress;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Copy, Clone)]
+/// places where a failure can be injected
+pub enum FaultSite {
+    /// `frame_alloc` and the frames claimed by the kernel heap
+    FrameAlloc = 0,
+    /// kernel heap allocations
+    HeapAlloc = 1,
+}
+
+/// number of [`FaultSite`]s
+pub const FAULT_SITES: usize = 2;
+
+/// fail the next `count` operations
+pub const FAULT_MODE_NEXT: usize = 0;
+/// fail every `count`-th operation
+pub const FAULT_MODE_EVERY_NTH: usize = 1;
+
+/// state of an injection site, disarmed while `count` is 0
+struct Injection {
+    task_id: AtomicUsize,
+    mode: AtomicUsize,
+    count: AtomicUsize,
+    /// operations seen in [`FAULT_MODE_EVERY_NTH`]
+    ops: AtomicUsize,
+}
+
+impl Injection {
+    const fn new() -> Self {
+        Self {
+            task_id: AtomicUsize::new(0),
+            mode: AtomicUsize::new(FAULT_MODE_NEXT),
+            count: AtomicUsize::new(0),
+            ops: AtomicUsize::new(0),
+        }
+    }
+}
+
+/// atomics only, since sites are checked inside the allocators
+static INJECTIONS: [Injection; FAULT_SITES] = [Injection::new(), Injection::new()];
+
+/// arm `site` for task `task_id`, a `count` of 0 disarms it
+pub fn arm(site: usize, task_id: usize, mode: usize, count: usize) {
+    let injection = &INJECTIONS[site];
+    injection.count.store(0, Ordering::Relaxed);
+    injection.task_id.store(task_id, Ordering::Relaxed);
+    injection.mode.store(mode, Ordering::Relaxed);
+    injection.ops.store(0, Ordering::Relaxed);
+    injection.count.store(count, Ordering::Relaxed);
+}
+
+/// whether the current operation at `site` should fail
+pub fn should_fail(site: FaultSite) -> bool {
+    let injection = &INJECTIONS[site as usize];
+    let count = injection.count.load(Ordering::Relaxed);
+    if count == 0 {
+        return false;
+    }
+    // the record of current task, also inside batch entries and after
+    // switching back from other tasks
+    match syscall_in_progress() {
+        Some((task_id, _)) if task_id == injection.task_id.load(Ordering::Relaxed) => {}
+        _ => return false,
+    }
+    if injection.mode.load(Ordering::Relaxed) == FAULT_MODE_NEXT {
+        injection.count.store(count - 1, Ordering::Relaxed);
+        true
+    } else {
+        (injection.ops.fetch_add(1, Ordering::Relaxed) + 1) % count == 0
+    }
+}
@@ -1,4 +1,5 @@
-use crate::sbi::shutdown;
+use crate::backtrace::kernel_backtrace;
+use crate::sbi::fail;
 use core::panic::PanicInfo;
 
 #[panic_handler]
@@ -13,5 +14,6 @@ fn panic(info: &PanicInfo) -> ! {
     } else {
         println!("Panicked: {}", info.message().unwrap());
     }
-    shutdown()
-}
\ No newline at end of file
+    kernel_backtrace();
+    fail()
+}
@@ -10,14 +10,22 @@ extern crate bitflags;
 
 #[macro_use]
 mod console;
+mod backtrace;
 mod config;
+mod drivers;
+#[cfg(feature = "fault_inject")]
+mod fault_inject;
 mod stack;
 mod lang_items;
 mod loader;
 mod mm;
+#[cfg(feature = "monitor")]
+mod monitor;
+mod rand;
 mod sbi;
 mod sync;
 mod timer;
+mod version;
 pub mod batch;
 pub mod syscall;
 pub mod task;
@@ -31,14 +39,28 @@ global_asm!(include_str!("link_app.S"));
 pub fn rust_main() -> ! {
     clear_bss();
     println!("[kernel] Hello, world!");
+    version::print_banner();
     mm::init();
     println!("[kernel] back to world!");
+    mm::frame_allocator_test();
+    mm::frame_allocator_contiguous_test();
     mm::remap_test();
+    mm::unmap_range_test();
+    #[cfg(feature = "frame_debug")]
+    mm::frame_debug_test();
+    #[cfg(feature = "heap_grow_test")]
+    mm::heap_grow_test();
+    #[cfg(feature = "backtrace_test")]
+    backtrace::backtrace_test();
     trap::init();
+    timer::init();
+    timer::init_realtime();
+    rand::init();
     trap::enable_timer_interrupt();
     // batch::init();
     // batch::run_next_app();
     // loader::load_apps();
+    task::init();
     timer::set_next_trigger();
     task::run_first_task();
     panic!("Unreachable in rust_main!");
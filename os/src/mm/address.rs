@@ -6,7 +6,7 @@ use core::fmt::{self, Debug, Formatter};
 
 /// physical address
 const PA_WIDTH_SV39: usize = 56;
-const VA_WIDTH_SV39: usize = 39;
+pub const VA_WIDTH_SV39: usize = 39;
 const PPN_WIDTH_SV39: usize = PA_WIDTH_SV39 - PAGE_SIZE_BITS;
 const VPN_WIDTH_SV39: usize = VA_WIDTH_SV39 - PAGE_SIZE_BITS;
 
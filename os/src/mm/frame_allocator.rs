@@ -1,12 +1,16 @@
 //! Implementation of [`FrameAllocator`] which
 //! controls all the frames in the operating system.
 
+#[cfg(feature = "frame_debug")]
+use super::frame_debug;
 use super::{PhysAddr, PhysPageNum};
 use crate::config::MEMORY_END;
-use crate::sync::UPSafeCell;
+#[cfg(feature = "fault_inject")]
+use crate::fault_inject::{should_fail, FaultSite};
+use crate::sync::{InitCell, UPSafeCell};
+use alloc::vec;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
-use lazy_static::*;
 
 /// manage a frame which has the same lifecycle as the tracker
 pub struct FrameTracker {
@@ -39,32 +43,57 @@ impl Drop for FrameTracker {
 trait FrameAllocator {
     fn new() -> Self;
     fn alloc(&mut self) -> Option<PhysPageNum>;
+    fn alloc_contiguous(&mut self, pages: usize) -> Option<PhysPageNum>;
     fn dealloc(&mut self, ppn: PhysPageNum);
+    /// number of free frames and of all frames
+    fn stats(&self) -> (usize, usize);
 }
 
 /// an implementation for frame allocator
 pub struct StackFrameAllocator {
+    start: usize,
     current: usize,
     end: usize,
     recycled: Vec<usize>,
+    /// one bit per frame in `[start, end)`, set while the frame is in `recycled`
+    recycled_bitmap: Vec<u64>,
 }
 
 impl StackFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
+        self.start = l.0;
         self.current = l.0;
         self.end = r.0;
+        self.recycled_bitmap = vec![0; (r.0 - l.0 + 63) / 64];
+    }
+    fn is_recycled(&self, ppn: usize) -> bool {
+        let idx = ppn - self.start;
+        self.recycled_bitmap[idx / 64] & (1 << (idx % 64)) != 0
+    }
+    fn set_recycled(&mut self, ppn: usize, recycled: bool) {
+        let idx = ppn - self.start;
+        if recycled {
+            self.recycled_bitmap[idx / 64] |= 1 << (idx % 64);
+        } else {
+            self.recycled_bitmap[idx / 64] &= !(1 << (idx % 64));
+        }
     }
 }
 impl FrameAllocator for StackFrameAllocator {
     fn new() -> Self {
         Self {
+            start: 0,
             current: 0,
             end: 0,
             recycled: Vec::new(),
+            recycled_bitmap: Vec::new(),
         }
     }
     fn alloc(&mut self) -> Option<PhysPageNum> {
         if let Some(ppn) = self.recycled.pop() {
+            self.set_recycled(ppn, false);
+            #[cfg(feature = "frame_debug")]
+            frame_debug::on_alloc_recycled(ppn.into());
             Some(ppn.into())
         } else if self.current == self.end {
             None
@@ -73,50 +102,132 @@ impl FrameAllocator for StackFrameAllocator {
             Some((self.current - 1).into())
         }
     }
+    /// Allocate `pages` physically contiguous frames, returning the first one.
+    ///
+    /// Prefers the never-allocated region, and falls back to searching the
+    /// bitmap for a run of recycled frames.
+    fn alloc_contiguous(&mut self, pages: usize) -> Option<PhysPageNum> {
+        if pages == 0 {
+            return None;
+        }
+        if self.end - self.current >= pages {
+            self.current += pages;
+            return Some((self.current - pages).into());
+        }
+        let mut run = 0;
+        for ppn in self.start..self.current {
+            run = if self.is_recycled(ppn) { run + 1 } else { 0 };
+            if run == pages {
+                let first = ppn + 1 - pages;
+                for p in first..=ppn {
+                    self.set_recycled(p, false);
+                    #[cfg(feature = "frame_debug")]
+                    frame_debug::on_alloc_recycled(p.into());
+                }
+                self.recycled.retain(|&p| p < first || p > ppn);
+                return Some(first.into());
+            }
+        }
+        None
+    }
     fn dealloc(&mut self, ppn: PhysPageNum) {
         let ppn = ppn.0;
         // validity check
-        if ppn >= self.current || self.recycled.iter().any(|&v| v == ppn) {
+        if ppn < self.start || ppn >= self.current || self.is_recycled(ppn) {
+            #[cfg(feature = "frame_debug")]
+            frame_debug::on_double_free(ppn.into());
             panic!("Frame ppn={:#x} has not been allocated!", ppn);
         }
+        #[cfg(feature = "frame_debug")]
+        frame_debug::on_dealloc(ppn.into());
         // recycle
+        self.set_recycled(ppn, true);
         self.recycled.push(ppn);
     }
+    fn stats(&self) -> (usize, usize) {
+        (
+            self.end - self.current + self.recycled.len(),
+            self.end - self.start,
+        )
+    }
 }
 
 type FrameAllocatorImpl = StackFrameAllocator;
 
-lazy_static! {
-    /// frame allocator instance through lazy_static!
-    pub static ref FRAME_ALLOCATOR: UPSafeCell<FrameAllocatorImpl> =
-        unsafe { UPSafeCell::new(FrameAllocatorImpl::new()) };
-}
+/// frame allocator instance, set up by [`init_frame_allocator`]
+pub static FRAME_ALLOCATOR: InitCell<UPSafeCell<FrameAllocatorImpl>> =
+    InitCell::new("FRAME_ALLOCATOR");
 
 /// initiate the frame allocator using `ekernel` and `MEMORY_END`
 pub fn init_frame_allocator() {
     extern "C" {
         fn ekernel();
     }
-    FRAME_ALLOCATOR.exclusive_access().init(
+    let mut allocator = FrameAllocatorImpl::new();
+    allocator.init(
         PhysAddr::from(ekernel as usize).ceil(),
         PhysAddr::from(MEMORY_END).floor(),
     );
+    FRAME_ALLOCATOR.init(unsafe { UPSafeCell::new(allocator) });
 }
 
 /// allocate a frame
 pub fn frame_alloc() -> Option<FrameTracker> {
+    #[cfg(feature = "fault_inject")]
+    if should_fail(FaultSite::FrameAlloc) {
+        return None;
+    }
     FRAME_ALLOCATOR
         .exclusive_access()
         .alloc()
         .map(FrameTracker::new)
 }
 
+/// allocate `pages` physically contiguous frames
+pub fn frame_alloc_contiguous(pages: usize) -> Option<Vec<FrameTracker>> {
+    FRAME_ALLOCATOR
+        .exclusive_access()
+        .alloc_contiguous(pages)
+        .map(|first| {
+            (first.0..first.0 + pages)
+                .map(|ppn| FrameTracker::new(ppn.into()))
+                .collect()
+        })
+}
+
+/// Allocate `pages` physically contiguous frames which are never freed, for
+/// the kernel heap.
+///
+/// The frames are not cleared, and `None` is returned if the frame allocator
+/// is busy, since this may be called while it is allocating memory, or not
+/// set up yet.
+pub fn frame_alloc_contiguous_raw(pages: usize) -> Option<PhysPageNum> {
+    #[cfg(feature = "fault_inject")]
+    if should_fail(FaultSite::FrameAlloc) {
+        return None;
+    }
+    FRAME_ALLOCATOR
+        .try_get()?
+        .try_exclusive_access()?
+        .alloc_contiguous(pages)
+}
+
+/// get the number of free frames and of all frames
+pub fn frame_stats() -> (usize, usize) {
+    FRAME_ALLOCATOR.exclusive_access().stats()
+}
+
+#[cfg(feature = "monitor")]
+/// Same as [`frame_stats`], but `None` if the frame allocator is busy.
+pub fn try_frame_stats() -> Option<(usize, usize)> {
+    Some(FRAME_ALLOCATOR.try_get()?.try_exclusive_access()?.stats())
+}
+
 /// deallocate a frame
 fn frame_dealloc(ppn: PhysPageNum) {
     FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
 }
 
-#[allow(unused)]
 /// a simple test for frame allocator
 pub fn frame_allocator_test() {
     let mut v: Vec<FrameTracker> = Vec::new();
@@ -134,3 +245,28 @@ pub fn frame_allocator_test() {
     drop(v);
     println!("frame_allocator_test passed!");
 }
+
+/// a stress test for contiguous allocation after heavy recycling
+pub fn frame_allocator_contiguous_test() {
+    let mut v: Vec<FrameTracker> = Vec::new();
+    while let Some(frame) = frame_alloc() {
+        v.push(frame);
+    }
+    // keep every other frame of the upper half, so only the lower half
+    // contains runs of free frames
+    let total = v.len();
+    let mut kept: Vec<FrameTracker> = Vec::new();
+    for (i, frame) in v.into_iter().enumerate() {
+        if i % 2 == 0 && i >= total / 2 {
+            kept.push(frame);
+        }
+    }
+    let run = frame_alloc_contiguous(16).unwrap();
+    for pair in run.windows(2) {
+        assert_eq!(pair[0].ppn.0 + 1, pair[1].ppn.0);
+    }
+    assert!(frame_alloc_contiguous(total).is_none());
+    drop(run);
+    drop(kept);
+    println!("frame_allocator_contiguous_test passed!");
+}
@@ -0,0 +1,101 @@
+//! Frame poisoning and double-free diagnostics, enabled by the
+//! `frame_debug` feature.
+//!
+//! Every recycled frame is filled with [`POISON`], and checked to still be
+//! poisoned when it is handed out again, catching writes after free. The
+//! last few frees are remembered together with a short backtrace, so that a
+//! double free can name both call sites.
+
+use super::{frame_alloc, PhysPageNum};
+use crate::backtrace::collect;
+use crate::sync::UPSafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
+
+/// byte pattern filled into freed frames
+const POISON: u8 = 0x5a;
+/// number of recent frees remembered
+const FREE_HISTORY_LEN: usize = 64;
+/// number of return addresses recorded per free
+const TRACE_DEPTH: usize = 4;
+
+type Backtrace = [usize; TRACE_DEPTH];
+
+/// number of writes after free reported so far
+static WRITES_AFTER_FREE: AtomicUsize = AtomicUsize::new(0);
+
+/// ring buffer of recently freed frames
+struct FreeHistory {
+    entries: [(usize, Backtrace); FREE_HISTORY_LEN],
+    next: usize,
+}
+
+lazy_static! {
+    static ref FREE_HISTORY: UPSafeCell<FreeHistory> = unsafe {
+        UPSafeCell::new(FreeHistory {
+            entries: [(0, [0; TRACE_DEPTH]); FREE_HISTORY_LEN],
+            next: 0,
+        })
+    };
+}
+
+/// return addresses of the caller, zero-padded
+#[inline(always)]
+fn backtrace() -> Backtrace {
+    let mut trace = [0; TRACE_DEPTH];
+    collect(&mut trace);
+    trace
+}
+
+/// poison a frame which is being freed and remember who freed it
+pub fn on_dealloc(ppn: PhysPageNum) {
+    ppn.get_bytes_array().fill(POISON);
+    let mut history = FREE_HISTORY.exclusive_access();
+    let next = history.next;
+    history.entries[next] = (ppn.0, backtrace());
+    history.next = (next + 1) % FREE_HISTORY_LEN;
+}
+
+/// check that a recycled frame was not written since it was freed
+pub fn on_alloc_recycled(ppn: PhysPageNum) {
+    if let Some(offset) = ppn.get_bytes_array().iter().position(|&b| b != POISON) {
+        WRITES_AFTER_FREE.fetch_add(1, Ordering::Relaxed);
+        error!(
+            "Frame ppn={:#x} was written after free, first at offset {:#x}",
+            ppn.0, offset
+        );
+    }
+}
+
+/// report both free sites of a frame which is freed twice
+pub fn on_double_free(ppn: PhysPageNum) -> ! {
+    let history = FREE_HISTORY.exclusive_access();
+    // search from the most recent free backwards
+    let last_free = (1..=FREE_HISTORY_LEN)
+        .map(|i| &history.entries[(history.next + FREE_HISTORY_LEN - i) % FREE_HISTORY_LEN])
+        .find(|(p, _)| *p == ppn.0);
+    if let Some((_, trace)) = last_free {
+        error!("Frame ppn={:#x} was first freed at {:x?}", ppn.0, trace);
+    }
+    drop(history);
+    let trace = backtrace();
+    error!("Frame ppn={:#x} is freed again at {:x?}", ppn.0, trace);
+    panic!("Frame ppn={:#x} has not been allocated!", ppn.0);
+}
+
+/// write through a stale reference to a freed frame, the next allocation
+/// should report it
+pub fn frame_debug_test() {
+    let frame = frame_alloc().unwrap();
+    let ppn = frame.ppn;
+    let stale = ppn.get_bytes_array();
+    drop(frame);
+    stale[0x123] = 0;
+    let reported = WRITES_AFTER_FREE.load(Ordering::Relaxed);
+    // the recycled list is a stack, so the same frame comes back
+    let frame = frame_alloc().unwrap();
+    assert_eq!(frame.ppn, ppn);
+    assert_eq!(WRITES_AFTER_FREE.load(Ordering::Relaxed), reported + 1);
+    drop(frame);
+    println!("frame_debug_test passed!");
+}
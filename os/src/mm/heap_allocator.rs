@@ -1,16 +1,130 @@
 //! The global allocator
+//!
+//! The heap starts as a static array of [`KERNEL_HEAP_SIZE`] bytes. When it
+//! is exhausted, it grows by claiming contiguous frames from the frame
+//! allocator, up to [`KERNEL_HEAP_GROW_LIMIT`] frames in total. Claimed
+//! frames are never returned.
 
-use crate::config::KERNEL_HEAP_SIZE;
+use super::frame_allocator::frame_alloc_contiguous_raw;
+use super::PhysAddr;
+use crate::config::{KERNEL_HEAP_GROW_LIMIT, KERNEL_HEAP_SIZE, PAGE_SIZE};
+#[cfg(feature = "fault_inject")]
+use crate::fault_inject::{should_fail, FaultSite};
+use crate::task::syscall_in_progress;
 use buddy_system_allocator::LockedHeap;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::{null_mut, NonNull};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// frames claimed at least per growth of the heap
+const HEAP_GROW_PAGES: usize = 16;
+
+/// buddy heap which grows with frames from the frame allocator
+pub struct GrowingHeap {
+    heap: LockedHeap,
+    /// frames claimed from the frame allocator so far
+    grown_pages: AtomicUsize,
+}
+
+impl GrowingHeap {
+    const fn empty() -> Self {
+        Self {
+            heap: LockedHeap::empty(),
+            grown_pages: AtomicUsize::new(0),
+        }
+    }
+    /// Claim enough frames for `layout`, returns false if the limit is
+    /// reached or no contiguous frames are left.
+    ///
+    /// Buddy blocks are aligned to their size while the frames are only
+    /// page aligned, so twice the block size is claimed to be sure that a
+    /// whole block fits.
+    fn grow(&self, layout: &Layout) -> bool {
+        let bytes = layout.size().max(layout.align());
+        let pages = 2 * bytes
+            .div_ceil(PAGE_SIZE)
+            .next_power_of_two()
+            .max(HEAP_GROW_PAGES);
+        let grown = self.grown_pages.load(Ordering::Relaxed);
+        if grown + pages > KERNEL_HEAP_GROW_LIMIT {
+            return false;
+        }
+        let first = match frame_alloc_contiguous_raw(pages) {
+            Some(first) => first,
+            None => return false,
+        };
+        let start = PhysAddr::from(first).0;
+        unsafe {
+            // the kernel maps all physical memory identically
+            self.heap
+                .lock()
+                .add_to_heap(start, start + pages * PAGE_SIZE);
+        }
+        self.grown_pages.store(grown + pages, Ordering::Relaxed);
+        true
+    }
+}
+
+unsafe impl GlobalAlloc for GrowingHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        #[cfg(feature = "fault_inject")]
+        if should_fail(FaultSite::HeapAlloc) {
+            return null_mut();
+        }
+        if let Ok(ptr) = self.heap.lock().alloc(layout) {
+            return ptr.as_ptr();
+        }
+        if !self.grow(&layout) {
+            return null_mut();
+        }
+        self.heap
+            .lock()
+            .alloc(layout)
+            .map_or(null_mut(), |ptr| ptr.as_ptr())
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.heap
+            .lock()
+            .dealloc(NonNull::new_unchecked(ptr), layout);
+    }
+}
 
 #[global_allocator]
 /// heap allocator instance
-static HEAP_ALLOCATOR: LockedHeap = LockedHeap::empty();
+static HEAP_ALLOCATOR: GrowingHeap = GrowingHeap::empty();
+
+#[derive(Copy, Clone)]
+/// usage of the kernel heap
+pub struct HeapStats {
+    /// bytes managed by the heap, including grown frames
+    pub total: usize,
+    /// bytes allocated
+    pub used: usize,
+    /// frames claimed from the frame allocator
+    pub grown_pages: usize,
+}
+
+/// get usage of the kernel heap
+pub fn heap_stats() -> HeapStats {
+    let heap = HEAP_ALLOCATOR.heap.lock();
+    HeapStats {
+        total: heap.stats_total_bytes(),
+        used: heap.stats_alloc_actual(),
+        grown_pages: HEAP_ALLOCATOR.grown_pages.load(Ordering::Relaxed),
+    }
+}
 
 #[alloc_error_handler]
-/// panic when heap allocation error occurs
+/// panic when heap allocation error occurs, naming the task whose syscall
+/// caused it
 pub fn handle_alloc_error(layout: core::alloc::Layout) -> ! {
-    panic!("Heap allocation error, layout = {:?}", layout);
+    match syscall_in_progress() {
+        Some((task_id, syscall_id)) => panic!(
+            "Heap allocation error in syscall {} of task {}, layout = {:?}",
+            syscall_id, task_id, layout
+        ),
+        None => panic!("Heap allocation error, layout = {:?}", layout),
+    }
 }
 
 /// heap space ([u8; KERNEL_HEAP_SIZE])
@@ -20,6 +134,7 @@ static mut HEAP_SPACE: [u8; KERNEL_HEAP_SIZE] = [0; KERNEL_HEAP_SIZE];
 pub fn init_heap() {
     unsafe {
         HEAP_ALLOCATOR
+            .heap
             .lock()
             .init(HEAP_SPACE.as_ptr() as usize, KERNEL_HEAP_SIZE);
     }
@@ -49,3 +164,41 @@ pub fn heap_test() {
     drop(v);
     println!("heap_test passed!");
 }
+
+#[allow(unused)]
+/// allocate past the static heap to check that the heap grows, and that the
+/// growth stops cleanly at the limit
+pub fn heap_grow_test() {
+    use alloc::vec::Vec;
+    let layout = Layout::from_size_align(HEAP_GROW_PAGES * PAGE_SIZE / 2, 8).unwrap();
+    let mut blocks = Vec::new();
+    // fill the static heap, then a large block needs a growth of its own,
+    // which must hold it however its frames are aligned
+    while heap_stats().grown_pages == 0 {
+        let ptr = unsafe { alloc::alloc::alloc(layout) };
+        assert!(!ptr.is_null());
+        blocks.push(ptr);
+    }
+    let mut large: Vec<u8> = Vec::new();
+    assert!(large.try_reserve_exact(256 * 1024).is_ok());
+    drop(large);
+    loop {
+        // the raw allocation returns null instead of calling the error handler
+        let ptr = unsafe { alloc::alloc::alloc(layout) };
+        if ptr.is_null() {
+            break;
+        }
+        blocks.push(ptr);
+    }
+    let stats = heap_stats();
+    assert!(blocks.len() * layout.size() > KERNEL_HEAP_SIZE);
+    assert!(stats.grown_pages > 0 && stats.grown_pages <= KERNEL_HEAP_GROW_LIMIT);
+    println!(
+        "heap grew by {} frames to {:#x} bytes",
+        stats.grown_pages, stats.total
+    );
+    for ptr in blocks {
+        unsafe { alloc::alloc::dealloc(ptr, layout) };
+    }
+    println!("heap_grow_test passed!");
+}
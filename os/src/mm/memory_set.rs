@@ -1,17 +1,21 @@
 //! Implementation of [`MapArea`] and [`MemorySet`].
 
-use super::{frame_alloc, FrameTracker};
+use super::vdso::vdso_ppn;
+use super::{frame_alloc, frame_stats, FrameTracker};
 use super::{PTEFlags, PageTable, PageTableEntry};
 use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use super::{StepByOne, VPNRange};
-use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT, USER_STACK_SIZE};
-use crate::sync::UPSafeCell;
+use crate::config::{
+    MEMORY_END, MMIO, PAGE_SIZE, PROC_INFO, TRAMPOLINE, TRAP_CONTEXT, USER_STACK_GROW_LIMIT,
+    USER_STACK_GROW_WINDOW, USER_STACK_SIZE, VDSO,
+};
+use crate::sync::{InitCell, UPSafeCell};
 use alloc::collections::BTreeMap;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::arch::asm;
-use lazy_static::*;
 use riscv::register::satp;
+pub use syscall_abi::AreaInfo;
 
 extern "C" {
     fn stext();
@@ -26,16 +30,25 @@ extern "C" {
     fn strampoline();
 }
 
-lazy_static! {
-    /// a memory set instance through lazy_static! managing kernel space
-    pub static ref KERNEL_SPACE: Arc<UPSafeCell<MemorySet>> =
-        Arc::new(unsafe { UPSafeCell::new(MemorySet::new_kernel()) });
+/// a memory set instance managing kernel space, set up by
+/// [`init_kernel_space`] once the frame allocator works
+pub static KERNEL_SPACE: InitCell<Arc<UPSafeCell<MemorySet>>> = InitCell::new("KERNEL_SPACE");
+
+/// map the kernel space
+pub fn init_kernel_space() {
+    KERNEL_SPACE.init(Arc::new(unsafe {
+        UPSafeCell::new(MemorySet::new_kernel())
+    }));
 }
 
 /// memory set structure, controls virtual-memory space
 pub struct MemorySet {
     page_table: PageTable,
     areas: Vec<MapArea>,
+    /// (area index, page offset) where [`MemorySet::scan_accessed`] resumes
+    scan_cursor: Option<(usize, usize)>,
+    /// lowest page the user stack may grow to and the page above its top
+    stack: Option<(VirtPageNum, VirtPageNum)>,
 }
 
 impl MemorySet {
@@ -43,6 +56,8 @@ impl MemorySet {
         Self {
             page_table: PageTable::new(),
             areas: Vec::new(),
+            scan_cursor: None,
+            stack: None,
         }
     }
     pub fn token(&self) -> usize {
@@ -60,10 +75,88 @@ impl MemorySet {
             None,
         );
     }
-    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
+    /// Unmap the pages in `[start_va, end_va)`, splitting areas which are
+    /// only partially covered.
+    ///
+    /// If `strict`, fails without changing anything when some page in the
+    /// range is not mapped; otherwise unmapped pages are skipped.
+    pub fn unmap_range(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        strict: bool,
+    ) -> Result<(), MmError> {
+        let start = start_va.floor();
+        let end = end_va.ceil();
+        let overlap = |area: &MapArea| {
+            let l = area.vpn_range.get_start().max(start);
+            let r = area.vpn_range.get_end().min(end);
+            if l < r {
+                Some((l, r))
+            } else {
+                None
+            }
+        };
+        if strict {
+            let covered: usize = self
+                .areas
+                .iter()
+                .filter_map(overlap)
+                .map(|(l, r)| r.0 - l.0)
+                .sum();
+            if covered < end.0.saturating_sub(start.0) {
+                return Err(MmError::NotMapped);
+            }
+        }
+        let mut areas = Vec::new();
+        for mut area in core::mem::take(&mut self.areas) {
+            let (l, r) = match overlap(&area) {
+                Some(range) => range,
+                None => {
+                    areas.push(area);
+                    continue;
+                }
+            };
+            // the cursor indexes `areas`, which is reordered from here on
+            self.scan_cursor = None;
+            if r < area.vpn_range.get_end() {
+                areas.push(area.split_off(r));
+            }
+            if l > area.vpn_range.get_start() {
+                let mut covered = area.split_off(l);
+                covered.unmap(&mut self.page_table);
+                areas.push(area);
+            } else {
+                area.unmap(&mut self.page_table);
+            }
+        }
+        self.areas = areas;
+        Ok(())
+    }
+    /// Remove the whole area starting at `start_vpn`, returns false if there
+    /// is no such area.
+    #[allow(unused)]
+    pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) -> bool {
+        let end_vpn = match self
+            .areas
+            .iter()
+            .find(|area| area.vpn_range.get_start() == start_vpn)
+        {
+            Some(area) => area.vpn_range.get_end(),
+            None => return false,
+        };
+        self.unmap_range(start_vpn.into(), end_vpn.into(), true)
+            .is_ok()
+    }
+    fn push(&mut self, map_area: MapArea, data: Option<&[u8]>) {
+        self.push_with_offset(map_area, 0, data);
+    }
+    /// Same as [`MemorySet::push`], but `data` starts `offset` bytes into the
+    /// first page of the area, as ELF segments at unaligned addresses do.
+    fn push_with_offset(&mut self, mut map_area: MapArea, offset: usize, data: Option<&[u8]>) {
         map_area.map(&mut self.page_table);
         if let Some(data) = data {
-            map_area.copy_data(&mut self.page_table, data);
+            map_area.copy_data(&mut self.page_table, data, offset);
         }
         self.areas.push(map_area);
     }
@@ -75,6 +168,15 @@ impl MemorySet {
             PTEFlags::R | PTEFlags::X,
         );
     }
+    /// Map the shared vdso page readable from user mode. Like the trampoline,
+    /// it is not collected by areas.
+    fn map_vdso(&mut self) {
+        self.page_table.map(
+            VirtAddr::from(VDSO).into(),
+            vdso_ppn(),
+            PTEFlags::R | PTEFlags::U,
+        );
+    }
     /// Without kernel stacks.
     pub fn new_kernel() -> Self {
         let mut memory_set = Self::new_bare();
@@ -138,14 +240,28 @@ impl MemorySet {
             ),
             None,
         );
+        println!("mapping memory-mapped registers");
+        for &(base, size) in MMIO {
+            memory_set.push(
+                MapArea::new(
+                    base.into(),
+                    (base + size).into(),
+                    MapType::Identical,
+                    MapPermission::R | MapPermission::W,
+                ),
+                None,
+            );
+        }
         memory_set
     }
-    /// Include sections in elf and trampoline and TrapContext and user stack,
-    /// also returns user_sp and entry point.
+    /// Include sections in elf and trampoline and TrapContext and user stack
+    /// and the vdso page, also returns user_sp and entry point.
     pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
         let mut memory_set = Self::new_bare();
         // map trampoline
         memory_set.map_trampoline();
+        // map vdso page
+        memory_set.map_vdso();
         // map program headers of elf, with U flag
         let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
         let elf_header = elf.header;
@@ -170,9 +286,11 @@ impl MemorySet {
                     map_perm |= MapPermission::X;
                 }
                 let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
-                max_end_vpn = map_area.vpn_range.get_end();
-                memory_set.push(
+                // the end is rounded up, and segments need not be sorted
+                max_end_vpn = max_end_vpn.max(map_area.vpn_range.get_end());
+                memory_set.push_with_offset(
                     map_area,
+                    start_va.page_offset(),
                     Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
                 );
             }
@@ -180,9 +298,15 @@ impl MemorySet {
         // map user stack with U flags
         let max_end_va: VirtAddr = max_end_vpn.into();
         let mut user_stack_bottom: usize = max_end_va.into();
-        // guard page
+        // guard page, then room for the stack to grow
         user_stack_bottom += PAGE_SIZE;
+        let user_stack_limit = user_stack_bottom;
+        user_stack_bottom += USER_STACK_GROW_LIMIT;
         let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
+        memory_set.stack = Some((
+            VirtAddr::from(user_stack_limit).floor(),
+            VirtAddr::from(user_stack_top).floor(),
+        ));
         memory_set.push(
             MapArea::new(
                 user_stack_bottom.into(),
@@ -192,6 +316,16 @@ impl MemorySet {
             ),
             None,
         );
+        // map the task information page, filled in by the task
+        memory_set.push(
+            MapArea::new(
+                PROC_INFO.into(),
+                VDSO.into(),
+                MapType::Framed,
+                MapPermission::R | MapPermission::U,
+            ),
+            None,
+        );
         // map TrapContext
         memory_set.push(
             MapArea::new(
@@ -218,6 +352,111 @@ impl MemorySet {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.page_table.translate(vpn)
     }
+    pub fn areas(&self) -> &[MapArea] {
+        &self.areas
+    }
+    /// Grow the user stack down to the page containing `addr`, if it is
+    /// above the growth limit and at most [`USER_STACK_GROW_WINDOW`] pages
+    /// below the stack.
+    ///
+    /// Returns whether the stack grew.
+    pub fn grow_stack(&mut self, addr: usize) -> bool {
+        let (limit, top) = match self.stack {
+            Some(stack) => stack,
+            None => return false,
+        };
+        let vpn = VirtPageNum(addr / PAGE_SIZE);
+        if vpn.0 < limit.0 || vpn.0 >= top.0 {
+            return false;
+        }
+        let area = match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range.get_end() == top)
+        {
+            Some(area) => area,
+            None => return false,
+        };
+        let bottom = area.vpn_range.get_start();
+        if vpn.0 >= bottom.0 || bottom.0 - vpn.0 > USER_STACK_GROW_WINDOW {
+            return false;
+        }
+        // new page table pages may be needed as well
+        let (free, _) = frame_stats();
+        if free < bottom.0 - vpn.0 + 2 {
+            return false;
+        }
+        area.grow_down(&mut self.page_table, vpn);
+        true
+    }
+    /// number of frames mapped into this address space, page tables excluded
+    pub fn resident_pages(&self) -> usize {
+        self.areas.iter().map(|area| area.data_frames.len()).sum()
+    }
+    /// whether a pass of [`MemorySet::scan_accessed`] is unfinished
+    pub fn scan_in_progress(&self) -> bool {
+        self.scan_cursor.is_some()
+    }
+    /// Read and clear the accessed bits of up to `budget` user pages,
+    /// resuming where the previous call stopped.
+    ///
+    /// Returns the number of pages scanned and whether the pass over all
+    /// areas has completed. Each area folds its count into its statistics
+    /// when the pass leaves it.
+    pub fn scan_accessed(&mut self, budget: usize) -> (usize, bool) {
+        let (mut idx, mut offset) = self.scan_cursor.take().unwrap_or((0, 0));
+        let mut scanned = 0;
+        let mut done = true;
+        while idx < self.areas.len() {
+            let area = &mut self.areas[idx];
+            let start = area.vpn_range.get_start().0;
+            let end = area.vpn_range.get_end().0;
+            if area.map_perm.contains(MapPermission::U) {
+                while start + offset < end && scanned < budget {
+                    if self.page_table.take_accessed(VirtPageNum(start + offset)) {
+                        area.access.touched += 1;
+                    }
+                    offset += 1;
+                    scanned += 1;
+                }
+                if start + offset < end {
+                    self.scan_cursor = Some((idx, offset));
+                    done = false;
+                    break;
+                }
+                area.access.fold();
+            }
+            idx += 1;
+            offset = 0;
+        }
+        if scanned > 0 {
+            // the cleared bits must be set again by the next access
+            unsafe {
+                asm!("sfence.vma");
+            }
+        }
+        (scanned, done)
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+/// page accesses of a map area found by [`MemorySet::scan_accessed`]
+struct AccessStats {
+    /// pages accessed so far in the current pass
+    touched: usize,
+    /// pages accessed during the last complete pass
+    last: usize,
+    /// moving average of `last`, scaled by 1000
+    hot: usize,
+}
+
+impl AccessStats {
+    /// end a pass
+    fn fold(&mut self) {
+        self.last = self.touched;
+        self.touched = 0;
+        self.hot = (self.hot + self.last * 1000) / 2;
+    }
 }
 
 /// map area structure, controls a contiguous piece of virtual memory
@@ -226,6 +465,7 @@ pub struct MapArea {
     data_frames: BTreeMap<VirtPageNum, FrameTracker>,
     map_type: MapType,
     map_perm: MapPermission,
+    access: AccessStats,
 }
 
 impl MapArea {
@@ -242,6 +482,7 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type,
             map_perm,
+            access: AccessStats::default(),
         }
     }
     pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
@@ -271,31 +512,71 @@ impl MapArea {
             self.map_one(page_table, vpn);
         }
     }
-    #[allow(unused)]
     pub fn unmap(&mut self, page_table: &mut PageTable) {
         for vpn in self.vpn_range {
             self.unmap_one(page_table, vpn);
         }
     }
-    /// data: start-aligned but maybe with shorter length
+    /// Extend this area down to `start` and map the new pages.
+    pub fn grow_down(&mut self, page_table: &mut PageTable, start: VirtPageNum) {
+        let end = self.vpn_range.get_start();
+        for vpn in VPNRange::new(start, end) {
+            self.map_one(page_table, vpn);
+        }
+        self.vpn_range = VPNRange::new(start, self.vpn_range.get_end());
+    }
+    /// Split this area at `vpn`, keeping `[start, vpn)` and returning
+    /// `[vpn, end)` together with its frames.
+    pub fn split_off(&mut self, vpn: VirtPageNum) -> MapArea {
+        let end = self.vpn_range.get_end();
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), vpn);
+        MapArea {
+            vpn_range: VPNRange::new(vpn, end),
+            data_frames: self.data_frames.split_off(&vpn),
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+            access: AccessStats::default(),
+        }
+    }
+    /// summary of this area as reported to user space
+    pub fn info(&self) -> AreaInfo {
+        AreaInfo {
+            start: VirtAddr::from(self.vpn_range.get_start()).into(),
+            end: VirtAddr::from(self.vpn_range.get_end()).into(),
+            perm: self.map_perm.bits as usize,
+            map_type: self.map_type as usize,
+            resident: match self.map_type {
+                MapType::Identical => self.vpn_range.get_end().0 - self.vpn_range.get_start().0,
+                MapType::Framed => self.data_frames.len(),
+            },
+            touched: self.access.last,
+            hot: self.access.hot,
+        }
+    }
+    /// data: starts `offset` bytes into the first page, maybe with shorter
+    /// length than the area
     /// assume that all frames were cleared before
-    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8]) {
+    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8], offset: usize) {
         assert_eq!(self.map_type, MapType::Framed);
+        let pages = self.vpn_range.get_end().0 - self.vpn_range.get_start().0;
+        assert!(
+            offset < PAGE_SIZE && offset + data.len() <= pages * PAGE_SIZE,
+            "data does not fit the map area"
+        );
         let mut start: usize = 0;
+        let mut page_offset = offset;
         let mut current_vpn = self.vpn_range.get_start();
         let len = data.len();
-        loop {
-            let src = &data[start..len.min(start + PAGE_SIZE)];
+        while start < len {
+            let chunk = (PAGE_SIZE - page_offset).min(len - start);
             let dst = &mut page_table
                 .translate(current_vpn)
                 .unwrap()
                 .ppn()
-                .get_bytes_array()[..src.len()];
-            dst.copy_from_slice(src);
-            start += PAGE_SIZE;
-            if start >= len {
-                break;
-            }
+                .get_bytes_array()[page_offset..page_offset + chunk];
+            dst.copy_from_slice(&data[start..start + chunk]);
+            start += chunk;
+            page_offset = 0;
             current_vpn.step();
         }
     }
@@ -304,8 +585,15 @@ impl MapArea {
 #[derive(Copy, Clone, PartialEq, Debug)]
 /// map type for memory set: identical or framed
 pub enum MapType {
-    Identical,
-    Framed,
+    Identical = 0,
+    Framed = 1,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+/// errors of memory set operations
+pub enum MmError {
+    /// part of the range is not mapped
+    NotMapped,
 }
 
 bitflags! {
@@ -318,6 +606,14 @@ bitflags! {
     }
 }
 
+// `AreaInfo::perm` carries these bits as the `AREA_PERM_*` of the user ABI
+const _: () = assert!(
+    MapPermission::R.bits as usize == syscall_abi::AREA_PERM_R
+        && MapPermission::W.bits as usize == syscall_abi::AREA_PERM_W
+        && MapPermission::X.bits as usize == syscall_abi::AREA_PERM_X
+        && MapPermission::U.bits as usize == syscall_abi::AREA_PERM_U
+);
+
 #[allow(unused)]
 pub fn remap_test() {
     let mut kernel_space = KERNEL_SPACE.exclusive_access();
@@ -341,3 +637,78 @@ pub fn remap_test() {
         .executable(),);
     println!("remap_test passed!");
 }
+
+#[allow(unused)]
+pub fn unmap_range_test() {
+    let mapped = |memory_set: &MemorySet, page: usize| {
+        memory_set
+            .translate(VirtPageNum(page))
+            .map_or(false, |pte| pte.is_valid())
+    };
+    let base = 0x10000;
+    let mut memory_set = MemorySet::new_bare();
+    memory_set.insert_framed_area(
+        VirtAddr::from(base * PAGE_SIZE),
+        VirtAddr::from((base + 16) * PAGE_SIZE),
+        MapPermission::R | MapPermission::W,
+    );
+    // punch a hole into the middle of the area
+    memory_set
+        .unmap_range(
+            VirtAddr::from((base + 4) * PAGE_SIZE),
+            VirtAddr::from((base + 8) * PAGE_SIZE),
+            true,
+        )
+        .unwrap();
+    for page in base..base + 16 {
+        assert_eq!(
+            mapped(&memory_set, page),
+            !(base + 4..base + 8).contains(&page)
+        );
+    }
+    assert_eq!(memory_set.areas.len(), 2);
+    // a strict unmap over the hole changes nothing
+    assert_eq!(
+        memory_set.unmap_range(
+            VirtAddr::from((base + 2) * PAGE_SIZE),
+            VirtAddr::from((base + 10) * PAGE_SIZE),
+            true,
+        ),
+        Err(MmError::NotMapped)
+    );
+    assert!(mapped(&memory_set, base + 2));
+    // a loose unmap straddling both areas trims each of them
+    memory_set
+        .unmap_range(
+            VirtAddr::from((base + 2) * PAGE_SIZE),
+            VirtAddr::from((base + 10) * PAGE_SIZE),
+            false,
+        )
+        .unwrap();
+    for page in base..base + 16 {
+        assert_eq!(
+            mapped(&memory_set, page),
+            !(base + 2..base + 10).contains(&page)
+        );
+    }
+    assert!(memory_set.remove_area_with_start_vpn(VirtPageNum(base + 10)));
+    assert!(!memory_set.remove_area_with_start_vpn(VirtPageNum(base + 10)));
+    assert!(!mapped(&memory_set, base + 15));
+    // an unmap drops a memwatch pass whose cursor it would invalidate
+    memory_set.insert_framed_area(
+        VirtAddr::from((base + 4) * PAGE_SIZE),
+        VirtAddr::from((base + 8) * PAGE_SIZE),
+        MapPermission::R | MapPermission::U,
+    );
+    memory_set.scan_accessed(1);
+    assert!(memory_set.scan_in_progress());
+    memory_set
+        .unmap_range(
+            VirtAddr::from((base + 4) * PAGE_SIZE),
+            VirtAddr::from((base + 5) * PAGE_SIZE),
+            true,
+        )
+        .unwrap();
+    assert!(!memory_set.scan_in_progress());
+    println!("unmap_range_test passed!");
+}
@@ -8,21 +8,38 @@
 
 mod address;
 mod frame_allocator;
+#[cfg(feature = "frame_debug")]
+mod frame_debug;
 mod heap_allocator;
 mod memory_set;
 mod page_table;
+mod vdso;
 
 pub use address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
-use address::{StepByOne, VPNRange};
-pub use frame_allocator::{frame_alloc, FrameTracker};
-pub use memory_set::remap_test;
-pub use memory_set::{MapPermission, MemorySet, KERNEL_SPACE};
-pub use page_table::{translated_byte_buffer, PageTableEntry};
+use address::{StepByOne, VPNRange, VA_WIDTH_SV39};
+#[cfg(feature = "monitor")]
+pub use frame_allocator::try_frame_stats;
+pub use frame_allocator::{frame_alloc, frame_alloc_contiguous, frame_stats, FrameTracker};
+pub use frame_allocator::{frame_allocator_contiguous_test, frame_allocator_test};
+#[cfg(feature = "frame_debug")]
+pub use frame_debug::frame_debug_test;
+#[cfg(feature = "heap_grow_test")]
+pub use heap_allocator::heap_grow_test;
+pub use heap_allocator::heap_stats;
+pub use memory_set::{remap_test, unmap_range_test};
+pub use memory_set::{AreaInfo, MapPermission, MemorySet, KERNEL_SPACE};
+pub use page_table::{
+    copy_from_user, copy_slice_to_user, copy_to_user, translated_byte_buffer,
+    translated_byte_buffer_checked, translated_byte_buffer_mut_checked, BadAddress, PageTableEntry,
+};
 use page_table::{PTEFlags, PageTable};
+pub use vdso::vdso_update_tick;
 
 /// initiate heap allocator, frame allocator and kernel space
 pub fn init() {
     heap_allocator::init_heap();
     frame_allocator::init_frame_allocator();
+    memory_set::init_kernel_space();
     KERNEL_SPACE.exclusive_access().activate();
+    vdso::init();
 }
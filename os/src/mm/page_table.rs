@@ -1,6 +1,9 @@
 //! Implementation of [`PageTableEntry`] and [`PageTable`].
 
-use super::{frame_alloc, FrameTracker, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use super::{
+    frame_alloc, FrameTracker, PhysPageNum, StepByOne, VirtAddr, VirtPageNum, VA_WIDTH_SV39,
+};
+use crate::config::PAGE_SIZE;
 use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
@@ -128,11 +131,24 @@ impl PageTable {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.find_pte(vpn).map(|pte| *pte)
     }
+    /// Clear the accessed bit of `vpn`, returning whether it was set.
+    ///
+    /// The caller must flush the TLB afterwards.
+    pub fn take_accessed(&mut self, vpn: VirtPageNum) -> bool {
+        match self.find_pte(vpn) {
+            Some(pte) if pte.is_valid() && pte.flags().contains(PTEFlags::A) => {
+                pte.bits &= !(PTEFlags::A.bits as usize);
+                true
+            }
+            _ => false,
+        }
+    }
     pub fn token(&self) -> usize {
         8usize << 60 | self.root_ppn.0
     }
 }
 
+#[allow(unused)]
 /// translate a pointer to a mutable u8 Vec through page table
 pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
     let page_table = PageTable::from_token(token);
@@ -155,3 +171,119 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
     }
     v
 }
+
+/// translate a pointer to a mutable u8 Vec through page table, stopping at the
+/// first page which is not readable from user mode.
+///
+/// The buffers cover a prefix of `[ptr, ptr + len)`; a total length shorter
+/// than `len` means the translation was truncated.
+pub fn translated_byte_buffer_checked(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+) -> Vec<&'static mut [u8]> {
+    translated_user_buffer(token, ptr as usize, len, PTEFlags::R)
+}
+
+/// same as [`translated_byte_buffer_checked`], but for a buffer the kernel
+/// is going to write, so stops at the first page not writable from user mode.
+pub fn translated_byte_buffer_mut_checked(
+    token: usize,
+    ptr: *mut u8,
+    len: usize,
+) -> Vec<&'static mut [u8]> {
+    translated_user_buffer(token, ptr as usize, len, PTEFlags::W)
+}
+
+#[derive(Debug)]
+/// the user memory range is not fully accessible with the required permission
+pub struct BadAddress;
+
+/// Copy a `T` from user space, byte by byte so that it may cross pages or be
+/// unaligned.
+pub fn copy_from_user<T: Copy>(token: usize, ptr: *const T) -> Result<T, BadAddress> {
+    let len = core::mem::size_of::<T>();
+    let mut val = core::mem::MaybeUninit::<T>::uninit();
+    let dst = unsafe { core::slice::from_raw_parts_mut(val.as_mut_ptr() as *mut u8, len) };
+    let mut start = 0;
+    let copied = for_each_user_page(token, ptr as usize, len, PTEFlags::R, |buffer| {
+        dst[start..start + buffer.len()].copy_from_slice(buffer);
+        start += buffer.len();
+    });
+    if copied < len {
+        return Err(BadAddress);
+    }
+    Ok(unsafe { val.assume_init() })
+}
+
+/// Copy `val` to user space, byte by byte so that it may cross pages or be
+/// unaligned. Nothing is written unless the whole range is writable.
+pub fn copy_to_user<T: Copy>(token: usize, ptr: *mut T, val: &T) -> Result<(), BadAddress> {
+    copy_slice_to_user(token, ptr, core::slice::from_ref(val))
+}
+
+/// slice version of [`copy_to_user`]
+pub fn copy_slice_to_user<T: Copy>(
+    token: usize,
+    ptr: *mut T,
+    vals: &[T],
+) -> Result<(), BadAddress> {
+    let len = core::mem::size_of_val(vals);
+    let src = unsafe { core::slice::from_raw_parts(vals.as_ptr() as *const u8, len) };
+    if for_each_user_page(token, ptr as usize, len, PTEFlags::W, |_| {}) < len {
+        return Err(BadAddress);
+    }
+    let mut start = 0;
+    for_each_user_page(token, ptr as usize, len, PTEFlags::W, |buffer| {
+        buffer.copy_from_slice(&src[start..start + buffer.len()]);
+        start += buffer.len();
+    });
+    Ok(())
+}
+
+fn translated_user_buffer(
+    token: usize,
+    ptr: usize,
+    len: usize,
+    access: PTEFlags,
+) -> Vec<&'static mut [u8]> {
+    let mut v = Vec::new();
+    for_each_user_page(token, ptr, len, access, |buffer| v.push(buffer));
+    v
+}
+
+/// Whether `va` is sign-extended from bit 38, as the MMU requires. Any other
+/// address would alias a canonical one once truncated to 39 bits, so a walk
+/// running into the gap between the halves stops there.
+fn is_canonical(va: usize) -> bool {
+    let high = va >> (VA_WIDTH_SV39 - 1);
+    high == 0 || high == usize::MAX >> (VA_WIDTH_SV39 - 1)
+}
+
+/// Call `f` on the part of `[ptr, ptr + len)` in each page, stopping at the
+/// first page not accessible from user mode with `access`, and return the
+/// number of bytes covered.
+///
+/// Nothing is allocated, so the copies above cannot fail for lack of heap.
+fn for_each_user_page(
+    token: usize,
+    ptr: usize,
+    len: usize,
+    access: PTEFlags,
+    mut f: impl FnMut(&'static mut [u8]),
+) -> usize {
+    let end = ptr.saturating_add(len);
+    let page_table = PageTable::from_token(token);
+    let mut start = ptr;
+    while start < end && is_canonical(start) {
+        let ppn = match page_table.translate(VirtAddr::from(start).floor()) {
+            Some(pte) if pte.flags().contains(PTEFlags::V | PTEFlags::U | access) => pte.ppn(),
+            _ => break,
+        };
+        let offset = start % PAGE_SIZE;
+        let chunk_end = (start - offset).saturating_add(PAGE_SIZE).min(end);
+        f(&mut ppn.get_bytes_array()[offset..offset + (chunk_end - start)]);
+        start = chunk_end;
+    }
+    start - ptr
+}
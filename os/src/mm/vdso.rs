@@ -0,0 +1,32 @@
+//! Data page shared read-only with every user address space, so that user
+//! programs can read kernel published values without a syscall.
+
+use super::{frame_alloc, FrameTracker, PhysPageNum};
+use crate::config::CLOCK_FREQ;
+use crate::sync::InitCell;
+use syscall_abi::VdsoData;
+
+/// frame of the shared page, allocated by [`init`] since it is updated from
+/// the timer interrupt handler
+static VDSO_FRAME: InitCell<FrameTracker> = InitCell::new("VDSO_FRAME");
+
+/// allocate and fill the shared page
+pub fn init() {
+    let frame = frame_alloc().unwrap();
+    let data = frame.ppn.get_mut::<VdsoData>();
+    data.clock_freq = CLOCK_FREQ;
+    data.last_tick = 0;
+    VDSO_FRAME.init(frame);
+}
+
+/// physical page of the shared page
+pub fn vdso_ppn() -> PhysPageNum {
+    VDSO_FRAME.get().ppn
+}
+
+/// publish the `time` counter of a timer interrupt
+pub fn vdso_update_tick(time: usize) {
+    if let Some(frame) = VDSO_FRAME.try_get() {
+        frame.ppn.get_mut::<VdsoData>().last_tick = time;
+    }
+}
@@ -0,0 +1,122 @@
+//! Debug monitor, entered by typing Ctrl-A three times within a second
+//!
+//! The console is polled for the sequence on every timer tick. The monitor
+//! then runs a command loop on polled console I/O with interrupts off and
+//! without heap allocation until `c` is typed. Ticks only come from user
+//! mode, when the kernel holds no borrow, but shared state is still borrowed
+//! with `try_` accessors so that a held borrow prints `<busy>` instead of
+//! panicking.
+//!
+//! Other console input is discarded, the kernel has no reader for it yet.
+
+use crate::config::PAGE_SIZE;
+use crate::mm::try_frame_stats;
+use crate::sbi::{console_getchar, console_putchar};
+use crate::task::{dump_task_regs, dump_tasks};
+use crate::timer::get_time_ms;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// returned by `console_getchar` if no input is pending
+const NO_INPUT: usize = usize::MAX;
+const CTRL_A: usize = 0x01;
+const CR: usize = 0x0d;
+const LF: usize = 0x0a;
+const BACKSPACE: usize = 0x08;
+const DELETE: usize = 0x7f;
+/// presses of Ctrl-A entering the monitor
+const PRESSES: usize = 3;
+/// all presses must happen within this many milliseconds
+const PRESS_WINDOW_MS: usize = 1000;
+/// longest command line
+const LINE_MAX: usize = 64;
+
+/// Ctrl-A presses seen in a row
+static PRESSED: AtomicUsize = AtomicUsize::new(0);
+/// time of the first of them
+static FIRST_PRESS_MS: AtomicUsize = AtomicUsize::new(0);
+
+/// Consume pending console input and enter the monitor on the magic
+/// sequence, called on every timer tick.
+pub fn poll() {
+    loop {
+        let c = console_getchar();
+        if c == NO_INPUT {
+            return;
+        }
+        if c != CTRL_A {
+            PRESSED.store(0, Ordering::Relaxed);
+            continue;
+        }
+        let now = get_time_ms();
+        let pressed = PRESSED.load(Ordering::Relaxed);
+        if pressed == 0 || now - FIRST_PRESS_MS.load(Ordering::Relaxed) > PRESS_WINDOW_MS {
+            FIRST_PRESS_MS.store(now, Ordering::Relaxed);
+            PRESSED.store(1, Ordering::Relaxed);
+        } else if pressed + 1 == PRESSES {
+            PRESSED.store(0, Ordering::Relaxed);
+            run();
+        } else {
+            PRESSED.store(pressed + 1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// read a line of printable characters into `buf` with echo, returns its
+/// length
+fn read_line(buf: &mut [u8; LINE_MAX]) -> usize {
+    let mut len = 0;
+    loop {
+        match console_getchar() {
+            CR | LF => {
+                println!("");
+                return len;
+            }
+            BACKSPACE | DELETE => {
+                if len > 0 {
+                    len -= 1;
+                    print!("\x08 \x08");
+                }
+            }
+            c @ 0x20..=0x7e if len < LINE_MAX => {
+                buf[len] = c as u8;
+                len += 1;
+                console_putchar(c);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// the command loop, returns on `c`
+fn run() {
+    println!("\n[monitor] entered, type `help` for commands");
+    let mut buf = [0u8; LINE_MAX];
+    loop {
+        print!("monitor> ");
+        let len = read_line(&mut buf);
+        // only printable ASCII is stored
+        let line = core::str::from_utf8(&buf[..len]).unwrap_or("");
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (None, _) => {}
+            (Some("ps"), None) => dump_tasks(),
+            (Some("bt"), Some(id)) => match id.parse() {
+                Ok(id) => dump_task_regs(id),
+                Err(_) => println!("bad task id {}", id),
+            },
+            (Some("mem"), None) => match try_frame_stats() {
+                Some((free, total)) => println!(
+                    "frames: {} KiB free of {} KiB",
+                    free * PAGE_SIZE / 1024,
+                    total * PAGE_SIZE / 1024
+                ),
+                None => println!("<busy>"),
+            },
+            (Some("c"), None) => {
+                println!("[monitor] continuing");
+                return;
+            }
+            _ => println!("commands: ps, bt <task id>, mem, c"),
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Kernel random number generator
+//!
+//! A ChaCha20 keystream generator whose key is seeded at boot from `mtime`
+//! samples and then continuously perturbed by interrupt arrival jitter
+//! gathered in [`add_entropy`].
+//!
+//! After every request the key is replaced with fresh keystream (fast key
+//! erasure), so a leaked state does not reveal output handed out before.
+
+use crate::sync::UPSafeCell;
+use crate::timer::get_time;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
+
+/// "expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// words of the entropy pool, as many bits as the generator key
+const POOL_WORDS: usize = 4;
+
+/// entropy pool, folded into the generator key on every request
+static ENTROPY_POOL: [AtomicUsize; POOL_WORDS] = [
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+    AtomicUsize::new(0),
+];
+/// pool word the next sample is mixed into
+static POOL_NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// mix a sample (e.g. the time an interrupt arrived) into the entropy pool
+pub fn add_entropy(sample: usize) {
+    let word = &ENTROPY_POOL[POOL_NEXT.fetch_add(1, Ordering::Relaxed) % POOL_WORDS];
+    let pool = word.load(Ordering::Relaxed);
+    word.store(pool.rotate_left(7) ^ sample, Ordering::Relaxed);
+}
+
+/// ChaCha20 based generator state
+pub struct ChaChaRng {
+    key: [u32; 8],
+    counter: u64,
+    block: [u8; 64],
+    /// number of bytes of `block` already handed out
+    used: usize,
+}
+
+impl ChaChaRng {
+    fn new() -> Self {
+        Self {
+            key: [0; 8],
+            counter: 0,
+            block: [0; 64],
+            used: 64,
+        }
+    }
+    /// fold the whole entropy pool into the key and discard buffered output
+    fn reseed(&mut self) {
+        for (i, word) in ENTROPY_POOL.iter().enumerate() {
+            let pool = word.swap(0, Ordering::Relaxed) as u64;
+            self.key[2 * i] ^= pool as u32;
+            self.key[2 * i + 1] ^= (pool >> 32) as u32;
+        }
+        self.rekey();
+    }
+    /// Replace the key with the next keystream block and discard the rest of
+    /// it, so that the old key cannot be recovered from the new state.
+    fn rekey(&mut self) {
+        self.refill();
+        for (i, word) in self.key.iter_mut().enumerate() {
+            *word = u32::from_le_bytes(self.block[i * 4..i * 4 + 4].try_into().unwrap());
+        }
+        self.block.fill(0);
+        self.used = self.block.len();
+    }
+    fn refill(&mut self) {
+        let mut state = [0u32; 16];
+        state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+        state[4..12].copy_from_slice(&self.key);
+        state[12] = self.counter as u32;
+        state[13] = (self.counter >> 32) as u32;
+        let input = state;
+        for _ in 0..10 {
+            quarter_round(&mut state, 0, 4, 8, 12);
+            quarter_round(&mut state, 1, 5, 9, 13);
+            quarter_round(&mut state, 2, 6, 10, 14);
+            quarter_round(&mut state, 3, 7, 11, 15);
+            quarter_round(&mut state, 0, 5, 10, 15);
+            quarter_round(&mut state, 1, 6, 11, 12);
+            quarter_round(&mut state, 2, 7, 8, 13);
+            quarter_round(&mut state, 3, 4, 9, 14);
+        }
+        for (i, word) in state.iter().enumerate() {
+            let word = word.wrapping_add(input[i]);
+            self.block[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+        }
+        self.counter += 1;
+        self.used = 0;
+    }
+    /// fill `dst` with random bytes
+    pub fn fill(&mut self, dst: &mut [u8]) {
+        for byte in dst {
+            if self.used == self.block.len() {
+                self.refill();
+            }
+            *byte = self.block[self.used];
+            self.used += 1;
+        }
+    }
+}
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+lazy_static! {
+    /// Global variable: RNG
+    pub static ref RNG: UPSafeCell<ChaChaRng> = unsafe { UPSafeCell::new(ChaChaRng::new()) };
+}
+
+/// seed the generator from a series of `mtime` samples
+pub fn init() {
+    let mut rng = RNG.exclusive_access();
+    for i in 0..32 {
+        // the amount of work between samples depends on the previous sample,
+        // so that small timing differences are amplified
+        let spin = get_time() & 0xff;
+        for _ in 0..spin {
+            core::hint::spin_loop();
+        }
+        add_entropy(get_time());
+        if i % 4 == 3 {
+            rng.reseed();
+        }
+    }
+}
+
+/// fill `dst` with random bytes, mixing in fresh entropy first
+pub fn fill_bytes(dst: &mut [u8]) {
+    let mut rng = RNG.exclusive_access();
+    rng.reseed();
+    rng.fill(dst);
+    rng.rekey();
+}
@@ -10,6 +10,18 @@ const SBI_REMOTE_SFENCE_VMA: usize = 6;
 const SBI_REMOTE_SFENCE_VMA_ASID: usize = 7;
 const SBI_SHUTDOWN: usize = 8;
 
+/// base extension, always present in SBI v0.2+
+const SBI_EXT_BASE: usize = 0x10;
+const SBI_BASE_PROBE_EXTENSION: usize = 3;
+/// system reset extension "SRST"
+const SBI_EXT_SRST: usize = 0x5352_5354;
+const SBI_SRST_SYSTEM_RESET: usize = 0;
+
+const SRST_TYPE_SHUTDOWN: usize = 0;
+const SRST_TYPE_COLD_REBOOT: usize = 1;
+const SRST_REASON_NONE: usize = 0;
+const SRST_REASON_FAILURE: usize = 1;
+
 use core::arch::asm;
 
 #[inline(always)]
@@ -27,6 +39,41 @@ fn sbi_call(which: usize, arg0: usize, arg1: usize, arg2: usize) -> usize {
     ret
 }
 
+/// call function `fid` of SBI extension `eid`, returns (error, value)
+#[inline(always)]
+fn sbi_call_ext(eid: usize, fid: usize, arg0: usize, arg1: usize) -> (isize, usize) {
+    let (error, value);
+    unsafe {
+        asm!(
+            "ecall",
+            inlateout("x10") arg0 => error,
+            inlateout("x11") arg1 => value,
+            in("x16") fid,
+            in("x17") eid,
+        );
+    }
+    (error, value)
+}
+
+/// whether the SBI implementation provides extension `eid`
+fn probe_extension(eid: usize) -> bool {
+    // legacy implementations fail the call with a negative error
+    let (error, value) = sbi_call_ext(SBI_EXT_BASE, SBI_BASE_PROBE_EXTENSION, eid, 0);
+    error == 0 && value != 0
+}
+
+/// Reset the system through the SRST extension, falling back to the legacy
+/// shutdown call if it is missing (which cannot reboot).
+fn system_reset(reset_type: usize, reason: usize) -> ! {
+    if probe_extension(SBI_EXT_SRST) {
+        println!("[kernel] system reset via SBI SRST");
+        sbi_call_ext(SBI_EXT_SRST, SBI_SRST_SYSTEM_RESET, reset_type, reason);
+    }
+    println!("[kernel] system reset via legacy SBI shutdown");
+    sbi_call(SBI_SHUTDOWN, 0, 0, 0);
+    panic!("It should shutdown!");
+}
+
 /// use sbi call to set timer
 pub fn set_timer(timer: usize) {
     sbi_call(SBI_SET_TIMER, timer, 0, 0);
@@ -36,7 +83,22 @@ pub fn console_putchar(c: usize) {
     sbi_call(SBI_CONSOLE_PUTCHAR, c, 0, 0);
 }
 
+/// read a byte from the console, `usize::MAX` if none is pending
+pub fn console_getchar() -> usize {
+    sbi_call(SBI_CONSOLE_GETCHAR, 0, 0, 0)
+}
+
+/// power off normally
 pub fn shutdown() -> ! {
-    sbi_call(SBI_SHUTDOWN, 0, 0, 0);
-    panic!("It should shutdown!");
+    system_reset(SRST_TYPE_SHUTDOWN, SRST_REASON_NONE)
+}
+
+/// power off reporting a failure, so that qemu exits with an error code
+pub fn fail() -> ! {
+    system_reset(SRST_TYPE_SHUTDOWN, SRST_REASON_FAILURE)
+}
+
+/// restart the machine
+pub fn reboot() -> ! {
+    system_reset(SRST_TYPE_COLD_REBOOT, SRST_REASON_NONE)
 }
@@ -0,0 +1,63 @@
+//! Explicitly initialized global data
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A global which is initialized once at a defined point of the boot
+/// sequence, unlike `lazy_static` which initializes on first touch, possibly
+/// from an interrupt handler.
+///
+/// We should only use it in uniprocessor.
+pub struct InitCell<T> {
+    /// name of the global, for error messages
+    name: &'static str,
+    initialized: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+unsafe impl<T> Sync for InitCell<T> {}
+
+impl<T> InitCell<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            initialized: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+    /// Initialize the data. Panic if it has been initialized.
+    pub fn init(&self, value: T) {
+        if self.initialized.load(Ordering::Acquire) {
+            panic!("{} is initialized twice", self.name);
+        }
+        unsafe {
+            (*self.value.get()).write(value);
+        }
+        self.initialized.store(true, Ordering::Release);
+    }
+    /// Get the data, or `None` if it has not been initialized yet.
+    pub fn try_get(&self) -> Option<&T> {
+        if self.initialized.load(Ordering::Acquire) {
+            Some(unsafe { (*self.value.get()).assume_init_ref() })
+        } else {
+            None
+        }
+    }
+    /// Get the data. Panic if it has not been initialized yet.
+    pub fn get(&self) -> &T {
+        match self.try_get() {
+            Some(value) => value,
+            None => panic!("{} is used before initialization", self.name),
+        }
+    }
+}
+
+impl<T> Deref for InitCell<T> {
+    type Target = T;
+    /// same as [`InitCell::get`]
+    fn deref(&self) -> &T {
+        self.get()
+    }
+}
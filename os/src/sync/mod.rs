@@ -1,5 +1,7 @@
 //! Synchronization and interior mutability primitives
 
+mod init;
 mod up;
 
+pub use init::InitCell;
 pub use up::UPSafeCell;
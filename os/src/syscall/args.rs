@@ -0,0 +1,151 @@
+//! Typed decoding of syscall arguments
+//!
+//! [`SyscallArgs`] holds the argument registers `a0`-`a5` of a syscall. Its
+//! accessors wrap an argument into the type a handler expects, which checks
+//! user memory and file descriptors the same way for every syscall when it
+//! is used.
+
+use super::errno::EBADF;
+use crate::mm::{
+    copy_from_user, copy_slice_to_user, copy_to_user, translated_byte_buffer_checked,
+    translated_byte_buffer_mut_checked, BadAddress,
+};
+use crate::task::current_user_token;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+/// number of syscall argument registers
+pub const SYSCALL_ARGS: usize = syscall_abi::MAX_SYSCALL_ARGS;
+
+const FD_STDIN: usize = 0;
+const FD_STDOUT: usize = 1;
+
+#[derive(Copy, Clone)]
+/// the argument registers `a0`-`a5` of a syscall
+pub struct SyscallArgs(pub [usize; SYSCALL_ARGS]);
+
+impl SyscallArgs {
+    /// argument `i` as an integer
+    pub fn usize(&self, i: usize) -> usize {
+        self.0[i]
+    }
+    /// argument `i` as a pointer to a `T` in user space
+    pub fn ptr<T>(&self, i: usize) -> UserPtr<T> {
+        UserPtr::new(self.0[i])
+    }
+    /// argument `i` as a buffer in user space whose length is argument `len`
+    pub fn slice(&self, i: usize, len: usize) -> UserSlice {
+        UserSlice {
+            ptr: self.0[i],
+            len: self.0[len],
+        }
+    }
+    /// argument `i` as a file descriptor
+    pub fn fd(&self, i: usize) -> Fd {
+        Fd(self.0[i])
+    }
+}
+
+/// a pointer to a `T` in user space, translated when accessed
+pub struct UserPtr<T> {
+    addr: usize,
+    _marker: PhantomData<*mut T>,
+}
+
+impl<T> Clone for UserPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for UserPtr<T> {}
+
+impl<T> UserPtr<T> {
+    pub fn new(addr: usize) -> Self {
+        Self {
+            addr,
+            _marker: PhantomData,
+        }
+    }
+    /// the `T` that is `count` elements after this one
+    pub fn add(&self, count: usize) -> Self {
+        Self::new(
+            self.addr
+                .wrapping_add(count.wrapping_mul(core::mem::size_of::<T>())),
+        )
+    }
+    /// whether the pointer is null, which some syscalls accept for an
+    /// output they can skip
+    pub fn is_null(&self) -> bool {
+        self.addr == 0
+    }
+}
+
+impl<T: Copy> UserPtr<T> {
+    /// copy the `T` from user space
+    pub fn read(&self) -> Result<T, BadAddress> {
+        copy_from_user(current_user_token(), self.addr as *const T)
+    }
+    /// copy `val` to user space, nothing is written unless all of it fits
+    pub fn write(&self, val: &T) -> Result<(), BadAddress> {
+        copy_to_user(current_user_token(), self.addr as *mut T, val)
+    }
+    /// copy `vals` to consecutive `T`s starting here
+    pub fn write_slice(&self, vals: &[T]) -> Result<(), BadAddress> {
+        copy_slice_to_user(current_user_token(), self.addr as *mut T, vals)
+    }
+}
+
+#[derive(Copy, Clone)]
+/// a byte buffer in user space, given by pointer and length
+pub struct UserSlice {
+    ptr: usize,
+    len: usize,
+}
+
+impl UserSlice {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// the at most `len` bytes starting `offset` bytes into this buffer
+    pub fn sub(&self, offset: usize, len: usize) -> Self {
+        let offset = offset.min(self.len);
+        Self {
+            ptr: self.ptr.wrapping_add(offset),
+            len: len.min(self.len - offset),
+        }
+    }
+    /// Translate the buffer for reading, stopping at the first page which is
+    /// not readable from user mode.
+    ///
+    /// A total length shorter than [`UserSlice::len`] means the translation
+    /// was truncated.
+    pub fn buffers(&self) -> Vec<&'static mut [u8]> {
+        translated_byte_buffer_checked(current_user_token(), self.ptr as *const u8, self.len)
+    }
+    /// same as [`UserSlice::buffers`], but for writing
+    pub fn buffers_mut(&self) -> Vec<&'static mut [u8]> {
+        translated_byte_buffer_mut_checked(current_user_token(), self.ptr as *mut u8, self.len)
+    }
+}
+
+/// the files a task can refer to, there is no fd table yet
+pub enum File {
+    Stdin,
+    Stdout,
+}
+
+#[derive(Copy, Clone)]
+/// a file descriptor, checked when it is used
+pub struct Fd(usize);
+
+impl Fd {
+    /// the file behind this descriptor, or `-EBADF`
+    pub fn file(&self) -> Result<File, isize> {
+        match self.0 {
+            FD_STDIN => Ok(File::Stdin),
+            FD_STDOUT => Ok(File::Stdout),
+            _ => Err(-EBADF),
+        }
+    }
+}
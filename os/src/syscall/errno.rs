@@ -0,0 +1,18 @@
+//! Error numbers, returned negated from syscalls
+
+/// Operation not permitted
+pub const EPERM: isize = 1;
+/// No such process
+pub const ESRCH: isize = 3;
+/// Out of memory
+pub const ENOMEM: isize = 12;
+/// Bad file number
+pub const EBADF: isize = 9;
+/// Bad address
+pub const EFAULT: isize = 14;
+/// Invalid argument
+pub const EINVAL: isize = 22;
+/// Not a typewriter
+pub const ENOTTY: isize = 25;
+/// Function not implemented
+pub const ENOSYS: isize = 38;
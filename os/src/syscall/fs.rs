@@ -1,21 +1,75 @@
 //! File and filesystem-related syscalls
 
-use crate::{mm::translated_byte_buffer, task::current_user_token};
+use super::args::{Fd, File, UserPtr, UserSlice};
+use super::errno::{EBADF, EFAULT, EINVAL, ENOTTY};
+use crate::console::{write_bytes, WinSize, CONSOLE_WINSIZE};
+use crate::mm::BadAddress;
+use crate::task::{account_console_write, cond_resched};
 
-const FD_STDOUT: usize = 1;
+/// get the console window size into a `WinSize`
+const TIOCGWINSZ: usize = 0x5413;
+/// set the console window size from a `WinSize`
+const TIOCSWINSZ: usize = 0x5414;
 
-/// write buf of length `len`  to a file with `fd`
-pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
-    match fd {
-        FD_STDOUT => {
-            let buffers = translated_byte_buffer(current_user_token(), buf, len);
-            for buffer in buffers {
-                print!("{}", core::str::from_utf8(buffer).unwrap());
+/// bytes of user memory translated at once, so that the kernel heap use of a
+/// single write does not grow with the requested length
+const WRITE_CHUNK: usize = 0x40_0000;
+
+/// write `buf` to a file with `fd`
+///
+/// Stops at the first unmapped page and returns the number of bytes actually
+/// written, or `-EFAULT` if not even the first byte is accessible.
+pub fn sys_write(fd: Fd, buf: UserSlice) -> isize {
+    match fd.file() {
+        Ok(File::Stdout) => {
+            let len = buf.len();
+            let mut written = 0;
+            while written < len {
+                let chunk = buf.sub(written, WRITE_CHUNK);
+                let mut done = 0;
+                for buffer in chunk.buffers() {
+                    write_bytes(buffer);
+                    done += buffer.len();
+                    // the console is slow, a page may take a while
+                    cond_resched();
+                }
+                written += done;
+                if done < chunk.len() {
+                    break;
+                }
             }
-            len as isize
-        },
-        _ => {
-            panic!("Unsupported fd in sys_write!");
+            account_console_write(written);
+            if len > 0 && written == 0 {
+                return -EFAULT;
+            }
+            written as isize
         }
+        Ok(File::Stdin) => -EBADF,
+        Err(errno) => errno,
+    }
+}
+
+/// control the terminal behind `fd`, only window size requests are supported
+pub fn sys_ioctl(fd: Fd, request: usize, arg: usize) -> isize {
+    if let Err(errno) = fd.file() {
+        return errno;
+    }
+    match request {
+        TIOCGWINSZ => {
+            let winsize = *CONSOLE_WINSIZE.exclusive_access();
+            match UserPtr::<WinSize>::new(arg).write(&winsize) {
+                Ok(()) => 0,
+                Err(BadAddress) => -EFAULT,
+            }
+        }
+        TIOCSWINSZ => match UserPtr::<WinSize>::new(arg).read() {
+            Ok(winsize) if winsize.rows == 0 || winsize.cols == 0 => -EINVAL,
+            Ok(winsize) => {
+                *CONSOLE_WINSIZE.exclusive_access() = winsize;
+                0
+            }
+            Err(BadAddress) => -EFAULT,
+        },
+        _ => -ENOTTY,
     }
 }
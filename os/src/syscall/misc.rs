@@ -0,0 +1,225 @@
+//! Miscellaneous syscalls
+
+use super::args::{UserPtr, UserSlice};
+use super::errno::{EFAULT, EINVAL, ENOMEM, ENOSYS};
+use super::{syscall, SYSCALL_ARGS};
+use crate::config::PAGE_SIZE;
+use crate::mm::{frame_stats, heap_stats, BadAddress};
+use crate::rand::fill_bytes;
+use crate::sync::UPSafeCell;
+use crate::task::{cond_resched, current_task_id};
+use crate::timer::{get_time_ms, uses_sstc};
+use crate::trap::hw_counters;
+use crate::version::uname;
+use alloc::vec::Vec;
+use lazy_static::*;
+use syscall_abi::{
+    Utsname, FEATURE_BATCH, FEATURE_FAULT_INJECT, FEATURE_FRAME_DEBUG, FEATURE_GETRANDOM,
+    FEATURE_HW_COUNTERS, FEATURE_IO_STATS, FEATURE_LOADAVG, FEATURE_MEMORY_MAP, FEATURE_MEMWATCH,
+    FEATURE_PROC_INFO, FEATURE_RUSAGE, FEATURE_SHUTDOWN, FEATURE_STRACE, FEATURE_SYSINFO,
+    FEATURE_THREAD_CPUTIME, FEATURE_UNAME, FEATURE_VDSO, SYSCALL_BATCH, SYSCALL_EXIT,
+    SYSCALL_SHUTDOWN,
+};
+
+/// upper bound of bytes filled by a single `sys_getrandom`
+const GETRANDOM_MAX: usize = 0x1_0000;
+
+/// fill `buf` with random bytes, returning the number of bytes written (at
+/// most 64KiB per call)
+pub fn sys_getrandom(buf: UserSlice) -> isize {
+    let buf = buf.sub(0, GETRANDOM_MAX);
+    let buffers = buf.buffers_mut();
+    if buf.len() > 0 && buffers.is_empty() {
+        return -EFAULT;
+    }
+    let mut filled = 0;
+    for buffer in buffers {
+        fill_bytes(buffer);
+        filled += buffer.len();
+        cond_resched();
+    }
+    filled as isize
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+/// system information returned by `sys_sysinfo`
+pub struct SysInfo {
+    /// milliseconds since boot
+    pub uptime_ms: usize,
+    /// bytes of physical memory managed by the frame allocator
+    pub total_ram: usize,
+    /// bytes of free frames
+    pub free_ram: usize,
+    /// bytes managed by the kernel heap
+    pub heap_total: usize,
+    /// bytes allocated from the kernel heap
+    pub heap_used: usize,
+    /// bytes of frames the kernel heap grew by
+    pub heap_grown: usize,
+    /// 1 if the timer is programmed through `stimecmp`, 0 if through SBI
+    pub timer_sstc: usize,
+}
+
+/// get memory usage and uptime
+pub fn sys_sysinfo(info: UserPtr<SysInfo>) -> isize {
+    let (free_frames, total_frames) = frame_stats();
+    let heap = heap_stats();
+    let sysinfo = SysInfo {
+        uptime_ms: get_time_ms(),
+        total_ram: total_frames * PAGE_SIZE,
+        free_ram: free_frames * PAGE_SIZE,
+        heap_total: heap.total,
+        heap_used: heap.used,
+        heap_grown: heap.grown_pages * PAGE_SIZE,
+        timer_sstc: uses_sstc() as usize,
+    };
+    match info.write(&sysinfo) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+/// get the name, version and build information of the kernel
+pub fn sys_uname(buf: UserPtr<Utsname>) -> isize {
+    match buf.write(&uname()) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+#[cfg(feature = "fault_inject")]
+/// Make the next `count` operations (`mode` 0), or every `count`-th
+/// operation (`mode` 1), at injection `site` fail for syscalls of the calling
+/// task. A `count` of 0 disarms the site.
+pub fn sys_fault_inject(site: usize, mode: usize, count: usize) -> isize {
+    use crate::fault_inject::{arm, FAULT_MODE_EVERY_NTH, FAULT_MODE_NEXT, FAULT_SITES};
+    if site >= FAULT_SITES || (mode != FAULT_MODE_NEXT && mode != FAULT_MODE_EVERY_NTH) {
+        return -EINVAL;
+    }
+    arm(site, current_task_id(), mode, count);
+    0
+}
+
+/// maximum number of entries of a single `sys_batch`
+const BATCH_MAX: usize = 64;
+/// syscalls which cannot be batched since they do not return
+const BATCH_DENYLIST: &[usize] = &[SYSCALL_EXIT, SYSCALL_SHUTDOWN, SYSCALL_BATCH];
+/// stop the batch if this entry fails
+const BATCH_STOP_ON_ERROR: usize = 1 << 0;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+/// one syscall of a `sys_batch`
+pub struct BatchEntry {
+    pub id: usize,
+    pub args: [usize; 3],
+    /// `BATCH_*` flags
+    pub flags: usize,
+    /// filled with the return value of the syscall
+    pub ret: isize,
+}
+
+/// Execute up to 64 syscalls described by `entries` in order, storing each
+/// return value into its entry.
+///
+/// Returns the number of entries executed, which is less than `count` if an
+/// entry with [`BATCH_STOP_ON_ERROR`] failed. Syscalls which do not return
+/// are refused with `-EINVAL` in their entry.
+pub fn sys_batch(entries: UserPtr<BatchEntry>, count: usize) -> isize {
+    if count > BATCH_MAX {
+        return -EINVAL;
+    }
+    let mut batch = Vec::new();
+    if batch.try_reserve_exact(count).is_err() {
+        return -ENOMEM;
+    }
+    for i in 0..count {
+        // writing the entry back unchanged checks that results can be stored
+        let entry = entries.add(i);
+        match entry.read() {
+            Ok(e) if entry.write(&e).is_ok() => batch.push(e),
+            _ => return -EFAULT,
+        }
+    }
+    for (i, mut e) in batch.into_iter().enumerate() {
+        e.ret = if BATCH_DENYLIST.contains(&e.id) {
+            -EINVAL
+        } else {
+            let mut args = [0; SYSCALL_ARGS];
+            args[..e.args.len()].copy_from_slice(&e.args);
+            syscall(e.id, args)
+        };
+        if entries.add(i).write(&e).is_err() {
+            return -EFAULT;
+        }
+        if e.ret < 0 && e.flags & BATCH_STOP_ON_ERROR != 0 {
+            return i as isize + 1;
+        }
+        cond_resched();
+    }
+    count as isize
+}
+
+/// optional features of this kernel build
+pub const KERNEL_FEATURES: usize = FEATURE_GETRANDOM
+    | FEATURE_MEMORY_MAP
+    | FEATURE_LOADAVG
+    | FEATURE_VDSO
+    | FEATURE_STRACE
+    | FEATURE_SHUTDOWN
+    | FEATURE_BATCH
+    | FEATURE_PROC_INFO
+    | FEATURE_SYSINFO
+    | FEATURE_IO_STATS
+    | FEATURE_MEMWATCH
+    | FEATURE_THREAD_CPUTIME
+    | FEATURE_RUSAGE
+    | FEATURE_UNAME
+    | if cfg!(feature = "frame_debug") {
+        FEATURE_FRAME_DEBUG
+    } else {
+        0
+    }
+    | if cfg!(feature = "fault_inject") {
+        FEATURE_FAULT_INJECT
+    } else {
+        0
+    };
+
+/// get the bitmap of optional features, so that the user library can probe
+/// which optional syscalls exist
+pub fn sys_kernel_features() -> isize {
+    let hw_counters = if hw_counters() {
+        FEATURE_HW_COUNTERS
+    } else {
+        0
+    };
+    (KERNEL_FEATURES | hw_counters) as isize
+}
+
+/// at most this many unsupported syscalls are reported
+const MAX_REPORTED_UNKNOWN: usize = 64;
+
+lazy_static! {
+    /// (task id, syscall id) pairs already reported as unsupported
+    static ref REPORTED_UNKNOWN: UPSafeCell<Vec<(usize, usize)>> =
+        unsafe { UPSafeCell::new(Vec::new()) };
+}
+
+/// Fail an unsupported syscall with `-ENOSYS`.
+///
+/// A warning is printed the first time each task calls each unsupported
+/// syscall, so a program spamming it does not flood the console.
+pub fn sys_unknown(syscall_id: usize, args: &[usize; SYSCALL_ARGS]) -> isize {
+    let task_id = current_task_id();
+    let mut reported = REPORTED_UNKNOWN.exclusive_access();
+    if reported.len() < MAX_REPORTED_UNKNOWN && !reported.contains(&(task_id, syscall_id)) {
+        reported.push((task_id, syscall_id));
+        warn!(
+            "[kernel] task {} called unsupported syscall {} with args {:x?}",
+            task_id, syscall_id, args
+        );
+    }
+    -ENOSYS
+}
@@ -10,24 +10,220 @@
 //! `sys_` then the name of the syscall. You can find functions like this in
 //! submodules, and you should also implement syscalls this way.
 
-const SYSCALL_WRITE: usize = 64;
-const SYSCALL_EXIT: usize = 93;
-const SYSCALL_YIELD: usize = 124;
-const SYSCALL_GET_TIME: usize = 169;
-
+mod args;
+mod errno;
 mod fs;
+mod misc;
 mod process;
+mod trace;
 
+use crate::task::{account_syscall, current_task_traced, set_syscall_in_progress};
+use args::{SyscallArgs, SYSCALL_ARGS};
 use fs::*;
+use misc::*;
 use process::*;
+use syscall_abi::*;
+use trace::Arg;
+
+/// handle syscall exception with `syscall_id` and the argument registers
+pub fn syscall(syscall_id: usize, args: [usize; SYSCALL_ARGS]) -> isize {
+    let args = SyscallArgs(args);
+    // batch entries are nested syscalls, restore the outer one afterwards
+    let outer = set_syscall_in_progress(Some(syscall_id));
+    account_syscall();
+    let ret = if current_task_traced() {
+        // exit never returns, so log it before it happens
+        if syscall_id == SYSCALL_EXIT {
+            trace::log(syscall_id, &args, None);
+        }
+        let ret = dispatch(syscall_id, &args);
+        trace::log(syscall_id, &args, Some(ret));
+        ret
+    } else {
+        dispatch(syscall_id, &args)
+    };
+    set_syscall_in_progress(outer);
+    ret
+}
+
+/// how a syscall is handled and traced
+struct Handler {
+    /// number of the syscall, checked against [`SYSCALLS`]
+    id: usize,
+    call: fn(&SyscallArgs) -> isize,
+    /// how strace prints each argument
+    args: &'static [Arg],
+}
+
+/// handlers of the syscalls in the order of [`SYSCALLS`]
+const HANDLERS: &[Handler] = &[
+    Handler {
+        id: SYSCALL_IOCTL,
+        call: |args| sys_ioctl(args.fd(0), args.usize(1), args.usize(2)),
+        args: &[Arg::Int, Arg::Hex, Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_WRITE,
+        call: |args| sys_write(args.fd(0), args.slice(1, 2)),
+        args: &[Arg::Int, Arg::Buf(2), Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_EXIT,
+        call: |args| sys_exit(args.usize(0) as i32),
+        args: &[Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_CLOCK_SETTIME,
+        call: |args| sys_clock_settime(args.usize(0), args.ptr(1)),
+        args: &[Arg::Int, Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_CLOCK_GETTIME,
+        call: |args| sys_clock_gettime(args.usize(0), args.ptr(1)),
+        args: &[Arg::Int, Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_CLOCK_GETRES,
+        call: |args| sys_clock_getres(args.usize(0), args.ptr(1)),
+        args: &[Arg::Int, Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_YIELD,
+        call: |_| sys_yield(),
+        args: &[],
+    },
+    Handler {
+        id: SYSCALL_UNAME,
+        call: |args| sys_uname(args.ptr(0)),
+        args: &[Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_GETRUSAGE,
+        call: |args| sys_getrusage(args.usize(0) as isize, args.ptr(1)),
+        args: &[Arg::Int, Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_GET_TIME,
+        call: |_| sys_get_time(),
+        args: &[],
+    },
+    Handler {
+        id: SYSCALL_GETPID,
+        call: |_| sys_getpid(),
+        args: &[],
+    },
+    Handler {
+        id: SYSCALL_GETRANDOM,
+        call: |args| sys_getrandom(args.slice(0, 1)),
+        args: &[Arg::Ptr, Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_GET_MEMORY_MAP,
+        call: |args| sys_get_memory_map(args.ptr(0), args.usize(1)),
+        args: &[Arg::Ptr, Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_LOADAVG,
+        call: |args| sys_loadavg(args.ptr(0)),
+        args: &[Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_KERNEL_FEATURES,
+        call: |_| sys_kernel_features(),
+        args: &[],
+    },
+    Handler {
+        id: SYSCALL_STRACE,
+        call: |args| sys_strace(args.usize(0), args.usize(1)),
+        args: &[Arg::Int, Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_SHUTDOWN,
+        call: |args| sys_shutdown(args.usize(0) != 0),
+        args: &[Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_BATCH,
+        call: |args| sys_batch(args.ptr(0), args.usize(1)),
+        args: &[Arg::Ptr, Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_SYSINFO,
+        call: |args| sys_sysinfo(args.ptr(0)),
+        args: &[Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_FAULT_INJECT,
+        call: fault_inject,
+        args: &[Arg::Int, Arg::Int, Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_GET_IO_STATS,
+        call: |args| sys_get_io_stats(args.usize(0), args.ptr(1)),
+        args: &[Arg::Int, Arg::Ptr],
+    },
+    Handler {
+        id: SYSCALL_MEMWATCH,
+        call: |args| sys_memwatch(args.usize(0)),
+        args: &[Arg::Int],
+    },
+    Handler {
+        id: SYSCALL_THREAD_CPUTIME,
+        call: |args| sys_thread_cputime(args.usize(0)),
+        args: &[Arg::Int],
+    },
+];
+
+// a syscall added to or renumbered in the ABI without updating the handlers
+// is caught here rather than by running the wrong handler
+const _: () = {
+    assert!(HANDLERS.len() == SYSCALLS.len());
+    let mut i = 0;
+    while i < HANDLERS.len() {
+        assert!(HANDLERS[i].id == SYSCALLS[i].id);
+        assert!(HANDLERS[i].args.len() == SYSCALLS[i].nargs);
+        i += 1;
+    }
+};
+
+#[cfg(feature = "fault_inject")]
+fn fault_inject(args: &SyscallArgs) -> isize {
+    sys_fault_inject(args.usize(0), args.usize(1), args.usize(2))
+}
+
+#[cfg(not(feature = "fault_inject"))]
+/// never called, [`FEATURE_FAULT_INJECT`] is not in [`KERNEL_FEATURES`]
+fn fault_inject(_args: &SyscallArgs) -> isize {
+    unreachable!()
+}
+
+/// the ABI entry and handler of syscall `syscall_id`, if this kernel build
+/// supports it
+fn lookup_syscall(syscall_id: usize) -> Option<(&'static Syscall, &'static Handler)> {
+    let i = lookup(syscall_id)?;
+    let syscall = &SYSCALLS[i];
+    if syscall.feature & !KERNEL_FEATURES == 0 {
+        Some((syscall, &HANDLERS[i]))
+    } else {
+        None
+    }
+}
 
-/// handle syscall exception with `syscall_id` and other arguments
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
-    match syscall_id {
-        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
-        SYSCALL_EXIT => sys_exit(args[0] as i32),
-        SYSCALL_YIELD => sys_yield(),
-        SYSCALL_GET_TIME => sys_get_time(),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+fn dispatch(syscall_id: usize, args: &SyscallArgs) -> isize {
+    match lookup_syscall(syscall_id) {
+        Some((syscall, handler)) => {
+            // garbage in unused argument registers usually means a caller
+            // built for another ABI, but the call is still made, the same in
+            // every build profile
+            if args.0[syscall.nargs..].iter().any(|&arg| arg != 0) {
+                warn!(
+                    "[kernel] {} called with unused args {:x?}",
+                    syscall.name,
+                    &args.0[syscall.nargs..]
+                );
+            }
+            (handler.call)(args)
+        }
+        None => sys_unknown(syscall_id, &args.0),
     }
 }
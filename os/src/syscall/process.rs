@@ -1,7 +1,19 @@
 //! Process management syscalls
+use super::args::UserPtr;
+use super::errno::{EFAULT, EINVAL, ENOMEM, EPERM, ESRCH};
 // use crate::batch::run_next_app;
-use crate::task::{exit_current_and_run_next, suspend_current_and_run_next};
-use crate::timer::get_time_ms;
+use crate::loader::get_num_app;
+use crate::mm::{AreaInfo, BadAddress};
+use crate::sbi;
+use crate::task::{
+    current_cpu_time_ns, current_load_avg, current_memory_map, current_rusage, current_task_id,
+    exit_current_and_run_next, set_current_memwatch, set_task_trace, suspend_current_and_run_next,
+    task_io_stats, IoStats, LoadAvg, RUsage,
+};
+use crate::timer::{
+    get_realtime_ns, get_time_ms, get_time_ns, set_realtime_ns, TimeSpec, NSEC_PER_SEC,
+    REALTIME_MAX_NS, TIME_RES_NS,
+};
 
 /// task exits and submit an exit code
 pub fn sys_exit(exit_code: i32) -> ! {
@@ -17,7 +29,185 @@ pub fn sys_yield() -> isize {
     0
 }
 
+/// get id of current task
+pub fn sys_getpid() -> isize {
+    current_task_id() as isize
+}
+
+/// Enable (`enable != 0`) or disable syscall tracing of task `pid`.
+///
+/// Tasks have no parent yet, so a task may only trace itself.
+pub fn sys_strace(pid: usize, enable: usize) -> isize {
+    if pid >= get_num_app() {
+        return -ESRCH;
+    }
+    if pid != current_task_id() {
+        return -EPERM;
+    }
+    set_task_trace(pid, enable != 0);
+    0
+}
+
+/// Get I/O accounting of task `pid` into `buf`.
+///
+/// Like `sys_strace`, a task may only query itself.
+pub fn sys_get_io_stats(pid: usize, buf: UserPtr<IoStats>) -> isize {
+    if pid >= get_num_app() {
+        return -ESRCH;
+    }
+    if pid != current_task_id() {
+        return -EPERM;
+    }
+    match buf.write(&task_io_stats(pid)) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+/// resource usage of the calling process
+const RUSAGE_SELF: isize = 0;
+/// resource usage of the terminated children of the calling process
+const RUSAGE_CHILDREN: isize = -1;
+
+/// Get resource usage of the caller (`who` is `RUSAGE_SELF`) or its children
+/// (`RUSAGE_CHILDREN`) into `buf`.
+///
+/// Tasks have no children yet, so their usage is all zero.
+pub fn sys_getrusage(who: isize, buf: UserPtr<RUsage>) -> isize {
+    let rusage = match who {
+        RUSAGE_SELF => current_rusage(),
+        RUSAGE_CHILDREN => RUsage::default(),
+        _ => return -EINVAL,
+    };
+    match buf.write(&rusage) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+/// power off the machine, or restart it if `reboot`
+pub fn sys_shutdown(reboot: bool) -> ! {
+    println!(
+        "[kernel] {} requested by task {}",
+        if reboot { "Reboot" } else { "Shutdown" },
+        current_task_id()
+    );
+    if reboot {
+        sbi::reboot()
+    } else {
+        sbi::shutdown()
+    }
+}
+
 /// get time in milliseconds
 pub fn sys_get_time() -> isize {
     get_time_ms() as isize
 }
+
+/// wall-clock time, settable
+const CLOCK_REALTIME: usize = 0;
+/// time since boot
+const CLOCK_MONOTONIC: usize = 1;
+/// CPU time of current process
+const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+/// CPU time of current thread, the same as the process for now
+const CLOCK_THREAD_CPUTIME_ID: usize = 3;
+
+/// get time of clock `clock_id` into `ts`
+pub fn sys_clock_gettime(clock_id: usize, ts: UserPtr<TimeSpec>) -> isize {
+    let ns = match clock_id {
+        CLOCK_REALTIME => get_realtime_ns(),
+        CLOCK_MONOTONIC => get_time_ns(),
+        CLOCK_PROCESS_CPUTIME_ID | CLOCK_THREAD_CPUTIME_ID => current_cpu_time_ns(),
+        _ => return -EINVAL,
+    };
+    match ts.write(&TimeSpec::from(ns)) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+/// Get CPU time of thread `tid` in microseconds.
+///
+/// Every process has a single thread, so only the caller (`tid` 0) can be
+/// queried.
+pub fn sys_thread_cputime(tid: usize) -> isize {
+    if tid != 0 {
+        return -ESRCH;
+    }
+    (current_cpu_time_ns() / 1000) as isize
+}
+
+/// Get the resolution of clock `clock_id` into `res`, unless `res` is null.
+///
+/// Every clock counts `time` ticks, even the CPU time ones, since tasks are
+/// charged by reading `time` at each switch.
+pub fn sys_clock_getres(clock_id: usize, res: UserPtr<TimeSpec>) -> isize {
+    match clock_id {
+        CLOCK_REALTIME | CLOCK_MONOTONIC | CLOCK_PROCESS_CPUTIME_ID | CLOCK_THREAD_CPUTIME_ID => {}
+        _ => return -EINVAL,
+    }
+    if res.is_null() {
+        return 0;
+    }
+    match res.write(&TimeSpec::from(TIME_RES_NS)) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+/// set time of clock `clock_id` from `ts`, only `CLOCK_REALTIME` can be set
+pub fn sys_clock_settime(clock_id: usize, ts: UserPtr<TimeSpec>) -> isize {
+    if clock_id != CLOCK_REALTIME {
+        return -EINVAL;
+    }
+    let ts = match ts.read() {
+        Ok(ts) => ts,
+        Err(BadAddress) => return -EFAULT,
+    };
+    // `sec` is a `time_t` in the ABI, so reject negative times as well as
+    // ones the wall clock cannot hold
+    if (ts.sec as isize) < 0 || ts.nsec as u64 >= NSEC_PER_SEC {
+        return -EINVAL;
+    }
+    match (ts.sec as u64)
+        .checked_mul(NSEC_PER_SEC)
+        .and_then(|ns| ns.checked_add(ts.nsec as u64))
+        .filter(|&ns| ns <= REALTIME_MAX_NS)
+    {
+        Some(ns) => {
+            set_realtime_ns(ns);
+            0
+        }
+        None => -EINVAL,
+    }
+}
+
+/// write up to `cap` [`AreaInfo`] records describing the address space of
+/// current task into `buf`, and return the total number of map areas
+pub fn sys_get_memory_map(buf: UserPtr<AreaInfo>, cap: usize) -> isize {
+    let areas = match current_memory_map() {
+        Some(areas) => areas,
+        None => return -ENOMEM,
+    };
+    let count = areas.len().min(cap);
+    match buf.write_slice(&areas[..count]) {
+        Ok(()) => areas.len() as isize,
+        Err(BadAddress) => -EFAULT,
+    }
+}
+
+/// Enable (`enable != 0`) or disable the accessed-bit scans of the address
+/// space of current task, whose results are reported by `sys_get_memory_map`.
+pub fn sys_memwatch(enable: usize) -> isize {
+    set_current_memwatch(enable != 0);
+    0
+}
+
+/// get load averages over 1s, 10s and 60s together with task counts
+pub fn sys_loadavg(buf: UserPtr<LoadAvg>) -> isize {
+    match buf.write(&current_load_avg()) {
+        Ok(()) => 0,
+        Err(BadAddress) => -EFAULT,
+    }
+}
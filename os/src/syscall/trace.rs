@@ -0,0 +1,157 @@
+//! Syscall tracing of tasks which enabled it with `sys_strace`
+//!
+//! Each traced syscall is logged as one line with its decoded arguments and
+//! return value, e.g. `task 3: write(1, "hello\n", 6) = 6`.
+
+use super::args::SyscallArgs;
+use super::errno::{EBADF, EFAULT, EINVAL, ENOMEM, ENOSYS, ENOTTY, EPERM, ESRCH};
+use super::lookup_syscall;
+use crate::mm::translated_byte_buffer_checked;
+use crate::sync::UPSafeCell;
+use crate::task::{current_task_id, current_user_token};
+use crate::timer::get_time_ms;
+use alloc::string::String;
+use core::fmt::Write;
+use lazy_static::*;
+
+#[derive(Copy, Clone)]
+/// how to print a syscall argument
+pub enum Arg {
+    /// signed integer
+    Int,
+    /// flags or request numbers, printed in hex
+    Hex,
+    /// user pointer, printed in hex
+    Ptr,
+    /// user buffer whose length is the argument at the given index, printed
+    /// as a string
+    Buf(usize),
+}
+
+/// names of the error numbers returned by syscalls
+const ERRNO_NAMES: &[(isize, &str)] = &[
+    (EPERM, "EPERM"),
+    (ESRCH, "ESRCH"),
+    (ENOMEM, "ENOMEM"),
+    (EBADF, "EBADF"),
+    (EFAULT, "EFAULT"),
+    (EINVAL, "EINVAL"),
+    (ENOTTY, "ENOTTY"),
+    (ENOSYS, "ENOSYS"),
+];
+
+/// at most this many bytes of a buffer argument are printed
+const STRING_CAP: usize = 32;
+/// at most this many lines are logged per second, over all tasks
+const TRACE_LINES_PER_SEC: usize = 100;
+
+/// state of the trace rate limit
+struct RateLimit {
+    /// start of the current one second window
+    window_start: usize,
+    /// lines logged in the current window
+    lines: usize,
+    /// lines dropped in the current window
+    dropped: usize,
+}
+
+lazy_static! {
+    static ref RATE_LIMIT: UPSafeCell<RateLimit> = unsafe {
+        UPSafeCell::new(RateLimit {
+            window_start: 0,
+            lines: 0,
+            dropped: 0,
+        })
+    };
+}
+
+/// whether another line may be logged now
+fn rate_limit_allows() -> bool {
+    let mut limit = RATE_LIMIT.exclusive_access();
+    let now = get_time_ms();
+    if now - limit.window_start >= 1000 {
+        if limit.dropped > 0 {
+            trace!("strace: {} lines dropped", limit.dropped);
+        }
+        limit.window_start = now;
+        limit.lines = 0;
+        limit.dropped = 0;
+    }
+    if limit.lines < TRACE_LINES_PER_SEC {
+        limit.lines += 1;
+        true
+    } else {
+        limit.dropped += 1;
+        false
+    }
+}
+
+/// append up to [`STRING_CAP`] bytes of a user buffer as an escaped string
+fn write_buffer(out: &mut String, ptr: usize, len: usize) {
+    let shown = len.min(STRING_CAP);
+    let buffers = translated_byte_buffer_checked(current_user_token(), ptr as *const u8, shown);
+    if buffers.iter().map(|buffer| buffer.len()).sum::<usize>() < shown {
+        let _ = write!(out, "{:#x}", ptr);
+        return;
+    }
+    out.push('"');
+    for &byte in buffers.iter().flat_map(|buffer| buffer.iter()) {
+        let _ = match byte {
+            b'\n' => write!(out, "\\n"),
+            b'"' | b'\\' => write!(out, "\\{}", byte as char),
+            0x20..=0x7e => write!(out, "{}", byte as char),
+            _ => write!(out, "\\x{:02x}", byte),
+        };
+    }
+    out.push('"');
+    if len > shown {
+        out.push_str("...");
+    }
+}
+
+/// Log a syscall of the current task, `ret` is `None` for syscalls which do
+/// not return.
+pub fn log(syscall_id: usize, args: &SyscallArgs, ret: Option<isize>) {
+    if !rate_limit_allows() {
+        return;
+    }
+    let mut line = String::new();
+    match lookup_syscall(syscall_id) {
+        Some((syscall, handler)) => {
+            let _ = write!(line, "{}(", syscall.name);
+            for (i, arg) in handler.args.iter().enumerate() {
+                if i > 0 {
+                    line.push_str(", ");
+                }
+                let _ = match *arg {
+                    Arg::Int => write!(line, "{}", args.usize(i) as isize),
+                    Arg::Hex | Arg::Ptr => write!(line, "{:#x}", args.usize(i)),
+                    Arg::Buf(len) => {
+                        write_buffer(&mut line, args.usize(i), args.usize(len));
+                        Ok(())
+                    }
+                };
+            }
+            line.push(')');
+        }
+        None => {
+            let _ = write!(
+                line,
+                "syscall_{}({:#x}, {:#x}, {:#x})",
+                syscall_id,
+                args.usize(0),
+                args.usize(1),
+                args.usize(2)
+            );
+        }
+    }
+    let _ = match ret {
+        Some(ret) if ret < 0 => match ERRNO_NAMES.iter().find(|(errno, _)| *errno == -ret) {
+            Some((_, name)) => write!(line, " = -1 {}", name),
+            None => write!(line, " = {}", ret),
+        },
+        Some(ret) => write!(line, " = {}", ret),
+        None => write!(line, " = ?"),
+    };
+    trace!("task {}: {}", current_task_id(), line);
+}
@@ -16,10 +16,13 @@ mod switch;
 mod task;
 
 use crate::loader::{get_app_data, get_num_app};
-use crate::sync::UPSafeCell;
+use crate::mm::AreaInfo;
+use crate::sbi::shutdown;
+use crate::sync::{InitCell, UPSafeCell};
+use crate::timer::{get_time, get_time_ms, ticks_to_ns, TIME_SLICE};
 use crate::trap::TrapContext;
 use alloc::vec::Vec;
-use lazy_static::*;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use switch::__switch;
 use task::{TaskControlBlock, TaskStatus};
 
@@ -47,28 +50,109 @@ pub struct TaskManagerInner {
     tasks: Vec<TaskControlBlock>,
     /// id of current `Running` task
     current_task: usize,
+    /// load averages over 1s, 10s and 60s, fixed point with [`FSHIFT`] bits
+    load_avg: [u64; 3],
+    /// time of the last switch to current task
+    switched_in: usize,
+    /// start of the time slice of current task, see [`cond_resched`]
+    slice_start: usize,
 }
 
-lazy_static! {
-    /// Global variable: TASK_MANAGER
-    pub static ref TASK_MANAGER: TaskManager = {
-        println!("init TASK_MANAGER");
-        let num_app = get_num_app();
-        println!("num_app = {}", num_app);
-        let mut tasks: Vec<TaskControlBlock> = Vec::new();
-        for i in 0..num_app {
-            tasks.push(TaskControlBlock::new(get_app_data(i), i));
-        }
-        TaskManager {
-            num_app,
-            inner: unsafe {
-                UPSafeCell::new(TaskManagerInner {
-                    tasks,
-                    current_task: 0,
-                })
-            },
-        }
-    };
+impl TaskManagerInner {
+    /// save the syscall in progress of current task and restore that of
+    /// task `next`
+    fn switch_syscall(&mut self, next: usize) {
+        let current = self.current_task;
+        self.tasks[current].syscall = set_syscall_in_progress(self.tasks[next].syscall);
+        SYSCALL_IN_PROGRESS[0].store(next, Ordering::Relaxed);
+    }
+
+    /// charge the time since the last switch to current task
+    fn charge_current(&mut self) {
+        let now = get_time();
+        let current = self.current_task;
+        self.tasks[current].cpu_time += now - self.switched_in;
+        self.switched_in = now;
+    }
+}
+
+/// fraction bits of fixed point load averages
+const FSHIFT: u64 = 16;
+/// 1.0 in fixed point
+const FIXED_1: u64 = 1 << FSHIFT;
+/// `exp(-1 / (ticks per second * period))` for the 1s, 10s and 60s periods
+/// in fixed point, with 100 timer ticks per second
+const LOAD_EXP: [u64; 3] = [64884, 65470, 65525];
+
+/// interval of the accessed-bit scans of a task watched by `sys_memwatch`
+const MEMWATCH_INTERVAL_MS: usize = 200;
+/// at most this many pages are scanned on each timer tick
+const MEMWATCH_PAGES_PER_TICK: usize = 256;
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+/// load information returned by `sys_loadavg`
+pub struct LoadAvg {
+    /// load averages over 1s, 10s and 60s, scaled by 1000
+    pub loads: [u64; 3],
+    /// number of processes alive
+    pub processes: u64,
+    /// number of threads alive
+    pub threads: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+/// I/O accounting of a task returned by `sys_get_io_stats`
+pub struct IoStats {
+    /// bytes written to the console
+    pub console_write_bytes: u64,
+    /// syscalls made, including the ones of a batch
+    pub syscalls: u64,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Default)]
+/// resource usage of a task returned by `sys_getrusage`
+pub struct RUsage {
+    /// CPU time in microseconds, including syscalls
+    pub cpu_time_us: u64,
+    /// peak resident set size in KiB
+    pub maxrss_kb: u64,
+    /// page faults resolved without I/O, e.g. by growing the stack
+    pub minflt: u64,
+    /// page faults which needed I/O, always 0 as nothing is paged in
+    pub majflt: u64,
+    /// times the task gave up the CPU itself
+    pub nvcsw: u64,
+    /// times the task was preempted by the timer
+    pub nivcsw: u64,
+}
+
+/// Global variable: TASK_MANAGER, set up by [`init`]
+pub static TASK_MANAGER: InitCell<TaskManager> = InitCell::new("TASK_MANAGER");
+
+/// load every app into its own address space, needs the kernel space
+pub fn init() {
+    println!("init TASK_MANAGER");
+    let num_app = get_num_app();
+    println!("num_app = {}", num_app);
+    let mut tasks: Vec<TaskControlBlock> = Vec::new();
+    for i in 0..num_app {
+        tasks.push(TaskControlBlock::new(get_app_data(i), i));
+    }
+    TASK_MANAGER.init(TaskManager {
+        num_app,
+        inner: unsafe {
+            UPSafeCell::new(TaskManagerInner {
+                tasks,
+                current_task: 0,
+                load_avg: [0; 3],
+                switched_in: 0,
+                slice_start: 0,
+            })
+        },
+    });
 }
 
 impl TaskManager {
@@ -81,6 +165,8 @@ impl TaskManager {
         let task0 = &mut inner.tasks[0];
         task0.task_status = TaskStatus::Running;
         let next_task_cx_ptr = &task0.task_cx as *const TaskContext;
+        inner.switched_in = get_time();
+        inner.slice_start = inner.switched_in;
         drop(inner);
         let mut _unused = TaskContext::zero_init();
         // before this, we should drop local variables that must be dropped manually
@@ -90,11 +176,18 @@ impl TaskManager {
         panic!("unreachable in run_first_task!");
     }
 
-    /// Change the status of current `Running` task into `Ready`.
-    fn mark_current_suspended(&self) {
+    /// Change the status of current `Running` task into `Ready`, counting a
+    /// context switch.
+    fn mark_current_suspended(&self, voluntary: bool) {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
-        inner.tasks[current].task_status = TaskStatus::Ready;
+        let task = &mut inner.tasks[current];
+        task.task_status = TaskStatus::Ready;
+        if voluntary {
+            task.rusage.nvcsw += 1;
+        } else {
+            task.rusage.nivcsw += 1;
+        }
     }
 
     /// Change the status of current `Running` task into `Exited`.
@@ -102,6 +195,13 @@ impl TaskManager {
         let mut inner = self.inner.exclusive_access();
         let current = inner.current_task;
         inner.tasks[current].task_status = TaskStatus::Exited;
+        #[cfg(feature = "kstack_watermark")]
+        println!(
+            "[kernel] task {} used {} of {} bytes of kernel stack",
+            current,
+            task::kernel_stack_high_water(current),
+            crate::config::KERNEL_STACK_SIZE
+        );
     }
 
     /// Find next task to run and return app id.
@@ -120,7 +220,10 @@ impl TaskManager {
     fn run_next_task(&self) {
         if let Some(next) = self.find_next_task() {
             let mut inner = self.inner.exclusive_access();
+            inner.charge_current();
+            inner.slice_start = inner.switched_in;
             let current = inner.current_task;
+            inner.switch_syscall(next);
             inner.tasks[next].task_status = TaskStatus::Running;
             inner.current_task = next;
             let current_task_cx_ptr = &mut inner.tasks[current].task_cx as *mut TaskContext;
@@ -132,7 +235,8 @@ impl TaskManager {
             }
             // go back to user mode
         } else {
-            panic!("All applications completed!");
+            println!("[kernel] All applications completed!");
+            shutdown();
         }
     }
 
@@ -142,11 +246,173 @@ impl TaskManager {
         inner.tasks[current].get_user_token()
     }
 
+    fn get_current_task_id(&self) -> usize {
+        self.inner.exclusive_access().current_task
+    }
+
+    /// whether current task has run for a whole time slice since it was
+    /// switched in
+    fn slice_expired(&self) -> bool {
+        get_time() - self.inner.exclusive_access().slice_start >= TIME_SLICE
+    }
+
+    fn get_current_cpu_time(&self) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        inner.charge_current();
+        inner.tasks[inner.current_task].cpu_time
+    }
+
+    fn set_task_trace(&self, task_id: usize, enable: bool) {
+        self.inner.exclusive_access().tasks[task_id].trace = enable;
+    }
+
+    fn is_current_traced(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        inner.tasks[inner.current_task].trace
+    }
+
+    fn account_current_io(&self, f: impl FnOnce(&mut IoStats)) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        f(&mut inner.tasks[current].io_stats);
+    }
+
+    fn get_io_stats(&self, task_id: usize) -> IoStats {
+        self.inner.exclusive_access().tasks[task_id].io_stats
+    }
+
+    fn grow_current_stack(&self, addr: usize) -> bool {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let task = &mut inner.tasks[current];
+        if !task.memory_set.grow_stack(addr) {
+            return false;
+        }
+        task.rusage.minflt += 1;
+        task.update_maxrss();
+        true
+    }
+
+    fn get_current_rusage(&self) -> RUsage {
+        let mut inner = self.inner.exclusive_access();
+        inner.charge_current();
+        let task = &inner.tasks[inner.current_task];
+        RUsage {
+            cpu_time_us: ticks_to_ns(task.cpu_time as u64) / 1000,
+            ..task.rusage
+        }
+    }
+
     fn get_current_trap_cx(&self) -> &mut TrapContext {
         let inner = self.inner.exclusive_access();
         let current = inner.current_task;
         inner.tasks[current].get_trap_cx()
     }
+
+    /// Fold the number of `Ready` and `Running` tasks into the load averages.
+    ///
+    /// Called on every timer tick.
+    fn sample_load(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let active = inner
+            .tasks
+            .iter()
+            .filter(|task| task.task_status != TaskStatus::Exited)
+            .count() as u64;
+        for (load, exp) in inner.load_avg.iter_mut().zip(LOAD_EXP) {
+            *load = (*load * exp + active * FIXED_1 * (FIXED_1 - exp)) >> FSHIFT;
+        }
+    }
+
+    fn set_current_memwatch(&self, enable: bool) {
+        let mut inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        inner.tasks[current].memwatch = enable.then(get_time_ms);
+    }
+
+    /// Continue the accessed-bit scans of watched tasks which are due,
+    /// scanning at most [`MEMWATCH_PAGES_PER_TICK`] pages.
+    ///
+    /// Called on every timer tick.
+    fn scan_memwatch(&self) {
+        let mut inner = self.inner.exclusive_access();
+        let now = get_time_ms();
+        let mut budget = MEMWATCH_PAGES_PER_TICK;
+        for task in inner.tasks.iter_mut() {
+            let next = match task.memwatch {
+                Some(next) if task.task_status != TaskStatus::Exited => next,
+                _ => continue,
+            };
+            if now < next && !task.memory_set.scan_in_progress() {
+                continue;
+            }
+            let (scanned, done) = task.memory_set.scan_accessed(budget);
+            if done {
+                task.memwatch = Some(now + MEMWATCH_INTERVAL_MS);
+            }
+            budget -= scanned;
+            if budget == 0 {
+                break;
+            }
+        }
+    }
+
+    fn get_load_avg(&self) -> LoadAvg {
+        let inner = self.inner.exclusive_access();
+        let alive = inner
+            .tasks
+            .iter()
+            .filter(|task| task.task_status != TaskStatus::Exited)
+            .count() as u64;
+        LoadAvg {
+            loads: inner.load_avg.map(|load| (load * 1000) >> FSHIFT),
+            // every task is a single threaded process for now
+            processes: alive,
+            threads: alive,
+        }
+    }
+
+    fn get_current_memory_map(&self) -> Option<Vec<AreaInfo>> {
+        let inner = self.inner.exclusive_access();
+        let current = inner.current_task;
+        let areas = inner.tasks[current].memory_set.areas();
+        let mut map = Vec::new();
+        map.try_reserve_exact(areas.len()).ok()?;
+        map.extend(areas.iter().map(|area| area.info()));
+        Some(map)
+    }
+}
+
+/// marks [`SYSCALL_IN_PROGRESS`] as empty
+const NO_SYSCALL: usize = usize::MAX;
+
+/// (task id, syscall id) of the syscall current task is handling
+///
+/// Only current task has its record here, the others keep theirs in their
+/// control block while switched out. Atomics so that the allocators can read
+/// it while `TASK_MANAGER` is borrowed.
+static SYSCALL_IN_PROGRESS: [AtomicUsize; 2] = [AtomicUsize::new(0), AtomicUsize::new(NO_SYSCALL)];
+
+/// get (task id, syscall id) of the syscall current task is handling, if any
+pub fn syscall_in_progress() -> Option<(usize, usize)> {
+    let task_id = SYSCALL_IN_PROGRESS[0].load(Ordering::Relaxed);
+    let syscall_id = SYSCALL_IN_PROGRESS[1].load(Ordering::Relaxed);
+    if syscall_id == NO_SYSCALL {
+        None
+    } else {
+        Some((task_id, syscall_id))
+    }
+}
+
+/// record that current task is handling `syscall_id`, or no syscall, and
+/// return the record replaced, so that a nested syscall can restore it
+pub fn set_syscall_in_progress(syscall_id: Option<usize>) -> Option<usize> {
+    let outer = SYSCALL_IN_PROGRESS[1].swap(syscall_id.unwrap_or(NO_SYSCALL), Ordering::Relaxed);
+    if outer == NO_SYSCALL {
+        None
+    } else {
+        Some(outer)
+    }
 }
 
 /// run first task
@@ -160,8 +426,8 @@ fn run_next_task() {
 }
 
 /// suspend current task
-fn mark_current_suspended() {
-    TASK_MANAGER.mark_current_suspended();
+fn mark_current_suspended(voluntary: bool) {
+    TASK_MANAGER.mark_current_suspended(voluntary);
 }
 
 /// exit current task
@@ -171,10 +437,28 @@ fn mark_current_exited() {
 
 /// suspend current task, then run next task
 pub fn suspend_current_and_run_next() {
-    mark_current_suspended();
+    mark_current_suspended(true);
+    run_next_task();
+}
+
+/// preempt current task when its time slice is used up, then run next task
+pub fn preempt_current_and_run_next() {
+    mark_current_suspended(false);
     run_next_task();
 }
 
+/// Preempt current task in the middle of a long syscall if it has used up its
+/// time slice.
+///
+/// Timer interrupts are off in the kernel, so syscalls which loop over a lot
+/// of data call this at their loop boundaries instead. Callers must not hold
+/// a borrow of any `UPSafeCell` across it.
+pub fn cond_resched() {
+    if TASK_MANAGER.slice_expired() {
+        preempt_current_and_run_next();
+    }
+}
+
 /// exit current task,  then run next task
 pub fn exit_current_and_run_next() {
     mark_current_exited();
@@ -185,6 +469,133 @@ pub fn current_user_token() -> usize {
     TASK_MANAGER.get_current_token()
 }
 
+/// get id of current `Running` task
+pub fn current_task_id() -> usize {
+    TASK_MANAGER.get_current_task_id()
+}
+
+/// get CPU time of current task in nanoseconds
+pub fn current_cpu_time_ns() -> u64 {
+    ticks_to_ns(TASK_MANAGER.get_current_cpu_time() as u64)
+}
+
+/// enable or disable syscall tracing of task `task_id`
+pub fn set_task_trace(task_id: usize, enable: bool) {
+    TASK_MANAGER.set_task_trace(task_id, enable);
+}
+
+/// whether the syscalls of current task are traced
+pub fn current_task_traced() -> bool {
+    TASK_MANAGER.is_current_traced()
+}
+
+#[cfg(feature = "monitor")]
+/// print id, status and user pc of every task for the debug monitor
+pub fn dump_tasks() {
+    let inner = match TASK_MANAGER
+        .try_get()
+        .and_then(|tm| tm.inner.try_exclusive_access())
+    {
+        Some(inner) => inner,
+        None => {
+            println!("<busy>");
+            return;
+        }
+    };
+    println!("  id status  pc");
+    for (id, task) in inner.tasks.iter().enumerate() {
+        let status = match task.task_status {
+            TaskStatus::Ready => "ready",
+            TaskStatus::Running => "running",
+            TaskStatus::Exited => "exited",
+        };
+        let current = if id == inner.current_task { '*' } else { ' ' };
+        println!(
+            "{}{:>3} {:<7} {:#x}",
+            current,
+            id,
+            status,
+            task.get_trap_cx().sepc
+        );
+    }
+}
+
+#[cfg(feature = "monitor")]
+/// print the saved user registers of task `task_id` for the debug monitor
+pub fn dump_task_regs(task_id: usize) {
+    let inner = match TASK_MANAGER
+        .try_get()
+        .and_then(|tm| tm.inner.try_exclusive_access())
+    {
+        Some(inner) => inner,
+        None => {
+            println!("<busy>");
+            return;
+        }
+    };
+    match inner.tasks.get(task_id) {
+        Some(task) => {
+            let cx = task.get_trap_cx();
+            println!(
+                "pc {:#x} ra {:#x} sp {:#x} fp {:#x}",
+                cx.sepc, cx.x[1], cx.x[2], cx.x[8]
+            );
+        }
+        None => println!("no task {}", task_id),
+    }
+}
+
+/// count a syscall of current task
+pub fn account_syscall() {
+    TASK_MANAGER.account_current_io(|io| io.syscalls += 1);
+}
+
+/// count bytes written to the console by current task
+pub fn account_console_write(bytes: usize) {
+    TASK_MANAGER.account_current_io(|io| io.console_write_bytes += bytes as u64);
+}
+
+/// get resource usage of current task
+pub fn current_rusage() -> RUsage {
+    TASK_MANAGER.get_current_rusage()
+}
+
+/// get I/O accounting of task `task_id`
+pub fn task_io_stats(task_id: usize) -> IoStats {
+    TASK_MANAGER.get_io_stats(task_id)
+}
+
+/// grow the user stack of current task to cover the faulting `addr`,
+/// returns whether it grew
+pub fn grow_current_stack(addr: usize) -> bool {
+    TASK_MANAGER.grow_current_stack(addr)
+}
+
 pub fn current_trap_cx() -> &'static mut TrapContext {
     TASK_MANAGER.get_current_trap_cx()
 }
+
+/// update load averages, called on every timer tick
+pub fn sample_load() {
+    TASK_MANAGER.sample_load();
+}
+
+/// enable or disable the accessed-bit scans of current task
+pub fn set_current_memwatch(enable: bool) {
+    TASK_MANAGER.set_current_memwatch(enable);
+}
+
+/// scan accessed bits of watched tasks, called on every timer tick
+pub fn scan_memwatch() {
+    TASK_MANAGER.scan_memwatch();
+}
+
+/// get load averages and task counts
+pub fn current_load_avg() -> LoadAvg {
+    TASK_MANAGER.get_load_avg()
+}
+
+/// get map areas of current task, or `None` if the kernel heap is exhausted
+pub fn current_memory_map() -> Option<Vec<AreaInfo>> {
+    TASK_MANAGER.get_current_memory_map()
+}
@@ -1,8 +1,28 @@
 //! Types related to task management
-use super::TaskContext;
-use crate::config::{kernel_stack_position, TRAP_CONTEXT};
+use super::{IoStats, RUsage, TaskContext};
+#[cfg(feature = "kstack_watermark")]
+use crate::config::KERNEL_STACK_SIZE;
+use crate::config::{kernel_stack_position, CLOCK_FREQ, PAGE_SIZE, PROC_INFO, TRAP_CONTEXT};
 use crate::mm::{MapPermission, MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE};
 use crate::trap::{trap_handler, TrapContext};
+use syscall_abi::{ProcInfo, PROC_INFO_MAGIC};
+
+#[cfg(feature = "kstack_watermark")]
+/// fills kernel stacks before first use, see [`kernel_stack_high_water`]
+const KSTACK_FILL: u8 = 0xcc;
+
+#[cfg(feature = "kstack_watermark")]
+/// Bytes of the kernel stack of task `app_id` ever used, found by the
+/// lowest byte which no longer holds [`KSTACK_FILL`].
+pub fn kernel_stack_high_water(app_id: usize) -> usize {
+    let (bottom, _) = kernel_stack_position(app_id);
+    let stack = unsafe { core::slice::from_raw_parts(bottom as *const u8, KERNEL_STACK_SIZE) };
+    let unused = stack
+        .iter()
+        .take_while(|&&byte| byte == KSTACK_FILL)
+        .count();
+    KERNEL_STACK_SIZE - unused
+}
 
 /// task control block structure
 pub struct TaskControlBlock {
@@ -11,6 +31,18 @@ pub struct TaskControlBlock {
     pub memory_set: MemorySet,
     pub trap_cx_ppn: PhysPageNum,
     pub base_size: usize,
+    /// log every syscall of this task, see `sys_strace`
+    pub trace: bool,
+    /// I/O accounting, see `sys_get_io_stats`
+    pub io_stats: IoStats,
+    /// time in ms of the next accessed-bit scan if enabled by `sys_memwatch`
+    pub memwatch: Option<usize>,
+    /// `time` counter ticks spent running, including syscalls
+    pub cpu_time: usize,
+    /// resource usage except CPU time, see `sys_getrusage`
+    pub rusage: RUsage,
+    /// syscall being handled while switched out, see `syscall_in_progress`
+    pub syscall: Option<usize>,
 }
 
 impl TaskControlBlock {
@@ -20,6 +52,11 @@ impl TaskControlBlock {
     pub fn get_user_token(&self) -> usize {
         self.memory_set.token()
     }
+    /// raise the peak resident set size to the current one
+    pub fn update_maxrss(&mut self) {
+        let kb = (self.memory_set.resident_pages() * PAGE_SIZE / 1024) as u64;
+        self.rusage.maxrss_kb = self.rusage.maxrss_kb.max(kb);
+    }
     pub fn new(elf_data: &[u8], app_id: usize) -> Self {
         // memory_set with elf program headers/trampoline/trap context/user stack
         let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
@@ -27,6 +64,17 @@ impl TaskControlBlock {
             .translate(VirtAddr::from(TRAP_CONTEXT).into())
             .unwrap()
             .ppn();
+        let proc_info = memory_set
+            .translate(VirtAddr::from(PROC_INFO).into())
+            .unwrap()
+            .ppn()
+            .get_mut::<ProcInfo>();
+        *proc_info = ProcInfo {
+            magic: PROC_INFO_MAGIC,
+            seq: 0,
+            pid: app_id,
+            clock_freq: CLOCK_FREQ,
+        };
         let task_status = TaskStatus::Ready;
         // map a kernel-stack in kernel space
         let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(app_id);
@@ -35,13 +83,25 @@ impl TaskControlBlock {
             kernel_stack_top.into(),
             MapPermission::R | MapPermission::W,
         );
-        let task_control_block = Self {
+        #[cfg(feature = "kstack_watermark")]
+        unsafe {
+            core::slice::from_raw_parts_mut(kernel_stack_bottom as *mut u8, KERNEL_STACK_SIZE)
+                .fill(KSTACK_FILL);
+        }
+        let mut task_control_block = Self {
             task_status,
             task_cx: TaskContext::goto_trap_return(kernel_stack_top),
             memory_set,
             trap_cx_ppn,
             base_size: user_sp,
+            trace: false,
+            io_stats: IoStats::default(),
+            memwatch: None,
+            cpu_time: 0,
+            rusage: RUsage::default(),
+            syscall: None,
         };
+        task_control_block.update_maxrss();
         // prepare TrapContext in user space
         let trap_cx = task_control_block.get_trap_cx();
         *trap_cx = TrapContext::app_init_context(
@@ -1,11 +1,108 @@
 //! RISC-V timer-related functionality
 
 use crate::config::CLOCK_FREQ;
+use crate::drivers::rtc::{self, RtcDevice};
 use crate::sbi::set_timer;
-use riscv::register::time;
+use core::arch::{asm, global_asm};
+use core::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use riscv::register::{stvec, time};
 
 const TICKS_PER_SEC: usize = 100;
-const MSEC_PER_SEC: usize = 1000;
+/// length of a time slice in `time` counter ticks
+pub const TIME_SLICE: usize = CLOCK_FREQ / TICKS_PER_SEC;
+pub const NSEC_PER_SEC: u64 = 1_000_000_000;
+const NSEC_PER_MSEC: u64 = 1_000_000;
+
+const fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// nanoseconds per `time` tick as the reduced fraction `NS_MUL / NS_DIV`,
+/// e.g. exactly 80 / 1 for the 12.5MHz clock of QEMU
+const NS_GCD: u64 = gcd(NSEC_PER_SEC, CLOCK_FREQ as u64);
+const NS_MUL: u64 = NSEC_PER_SEC / NS_GCD;
+const NS_DIV: u64 = CLOCK_FREQ as u64 / NS_GCD;
+
+/// resolution of times read from the `time` counter, rounded up to whole
+/// nanoseconds
+pub const TIME_RES_NS: u64 = (NS_MUL + NS_DIV - 1) / NS_DIV;
+
+/// wall-clock time at boot in nanoseconds since the Unix epoch, negative if
+/// the clock was set to a time before the current uptime
+static REALTIME_OFFSET_NS: AtomicI64 = AtomicI64::new(0);
+/// the timer is programmed through `stimecmp` instead of SBI calls
+static SSTC: AtomicBool = AtomicBool::new(false);
+
+// Trap handler of `probe_sstc`: skip the faulting instruction and report
+// failure in a0.
+global_asm!(
+    "
+    .section .text
+    .align 2
+    .globl __sstc_probe_trap
+__sstc_probe_trap:
+    csrr t0, sepc
+    addi t0, t0, 4
+    csrw sepc, t0
+    li a0, 0
+    sret
+"
+);
+
+/// Whether `stimecmp` is accessible, i.e. the hart has Sstc and the SBI
+/// implementation enabled it for S mode.
+///
+/// The CSR is read with a temporary trap handler, since the access raises an
+/// illegal instruction exception otherwise. Interrupts must be off.
+fn probe_sstc() -> bool {
+    extern "C" {
+        fn __sstc_probe_trap();
+    }
+    let saved = stvec::read();
+    let mut ok: usize = 1;
+    unsafe {
+        stvec::write(__sstc_probe_trap as usize, stvec::TrapMode::Direct);
+        asm!("csrr {}, 0x14d", out(reg) _, inout("a0") ok, out("t0") _);
+        stvec::write(saved.address(), saved.trap_mode().unwrap());
+    }
+    ok != 0
+}
+
+/// probe for Sstc, must be called before the first [`set_next_trigger`]
+pub fn init() {
+    let sstc = probe_sstc();
+    SSTC.store(sstc, Ordering::Relaxed);
+    println!(
+        "[kernel] timer programmed via {}",
+        if sstc { "stimecmp (Sstc)" } else { "SBI" }
+    );
+}
+
+/// whether the timer is programmed through `stimecmp`
+pub fn uses_sstc() -> bool {
+    SSTC.load(Ordering::Relaxed)
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+/// time in seconds and nanoseconds, as `struct timespec` of Linux
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+impl From<u64> for TimeSpec {
+    fn from(ns: u64) -> Self {
+        Self {
+            sec: (ns / NSEC_PER_SEC) as usize,
+            nsec: (ns % NSEC_PER_SEC) as usize,
+        }
+    }
+}
 
 /// read the `mtime` register
 pub fn get_time() -> usize {
@@ -14,10 +111,57 @@ pub fn get_time() -> usize {
 
 /// get current time in milliseconds
 pub fn get_time_ms() -> usize {
-    time::read() / (CLOCK_FREQ / MSEC_PER_SEC)
+    (get_time_ns() / NSEC_PER_MSEC) as usize
+}
+
+/// Convert `time` counter ticks to nanoseconds.
+///
+/// The product is taken in 128 bits, so it neither overflows for long
+/// uptimes nor loses the fraction of a tick to rounding.
+pub fn ticks_to_ns(ticks: u64) -> u64 {
+    (ticks as u128 * NS_MUL as u128 / NS_DIV as u128) as u64
+}
+
+/// get time since boot in nanoseconds
+pub fn get_time_ns() -> u64 {
+    ticks_to_ns(get_time() as u64)
+}
+
+/// get wall-clock time in nanoseconds since the Unix epoch
+pub fn get_realtime_ns() -> u64 {
+    let ns = REALTIME_OFFSET_NS
+        .load(Ordering::Relaxed)
+        .saturating_add(get_time_ns() as i64);
+    ns.max(0) as u64
+}
+
+/// latest wall-clock time that can be set, in nanoseconds
+pub const REALTIME_MAX_NS: u64 = i64::MAX as u64;
+
+/// set wall-clock time, only the offset to the boot time is changed
+///
+/// Times after [`REALTIME_MAX_NS`] are clamped to it.
+pub fn set_realtime_ns(ns: u64) {
+    let ns = ns.min(REALTIME_MAX_NS) as i64;
+    REALTIME_OFFSET_NS.store(ns - get_time_ns() as i64, Ordering::Relaxed);
+}
+
+/// seed the wall-clock time from the RTC, if the board has one
+pub fn init_realtime() {
+    match rtc::probe() {
+        Some(rtc) => set_realtime_ns(rtc.read_time_ns()),
+        None => println!("[kernel] no RTC found, wall-clock time starts at the epoch"),
+    }
 }
 
 /// set the next timer interrupt
 pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
+    let deadline = get_time() + TIME_SLICE;
+    if uses_sstc() {
+        unsafe {
+            asm!("csrw 0x14d, {}", in(reg) deadline);
+        }
+    } else {
+        set_timer(deadline);
+    }
 }
@@ -1,7 +1,13 @@
 //! Implementation of [`TrapContext`]
 
+use core::arch::asm;
 use riscv::register::sstatus::{self, Sstatus, SPP};
 
+/// the FS field of sstatus, state of the FP registers
+const SSTATUS_FS: usize = 3 << 13;
+/// FS is Initial: FP is enabled and the registers are all zero
+const SSTATUS_FS_INITIAL: usize = 1 << 13;
+
 #[repr(C)]
 /// trap context structure containing sstatus, sepc and registers
 pub struct TrapContext {
@@ -17,6 +23,10 @@ pub struct TrapContext {
     pub kernel_sp: usize,
     /// Addr of trap_handler function
     pub trap_handler: usize,
+    /// FP regs f[0..31], saved only when sstatus.FS is Dirty
+    pub f: [usize; 32],
+    /// CSR fcsr, saved with the FP regs
+    pub fcsr: usize,
 }
 
 impl TrapContext {
@@ -32,6 +42,15 @@ impl TrapContext {
         kernel_sp: usize,
         trap_handler: usize,
     ) -> Self {
+        // apps start with FP enabled and zeroed FP registers
+        unsafe {
+            asm!(
+                "csrc sstatus, {fs}",
+                "csrs sstatus, {initial}",
+                fs = in(reg) SSTATUS_FS,
+                initial = in(reg) SSTATUS_FS_INITIAL,
+            );
+        }
         let mut sstatus = sstatus::read(); // CSR sstatus
         sstatus.set_spp(SPP::User); //previous privilege mode: user mode
         let mut cx = Self {
@@ -41,6 +60,8 @@ impl TrapContext {
             kernel_satp,  // addr of page table
             kernel_sp,    // kernel stack
             trap_handler, // addr of trap_handler function
+            f: [0; 32],
+            fcsr: 0,
         };
         cx.set_sp(sp); // app's user stack pointer
         cx // return initial Trap Context of app
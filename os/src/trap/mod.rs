@@ -14,29 +14,72 @@
 
 mod context;
 
+use crate::backtrace::Symbol;
 use crate::config::{TRAP_CONTEXT, TRAMPOLINE};
+use crate::mm::{copy_from_user, vdso_update_tick};
+use crate::rand::add_entropy;
 use crate::syscall::syscall;
-use crate::task::{exit_current_and_run_next, suspend_current_and_run_next, current_trap_cx, current_user_token};
-use crate::timer::set_next_trigger;
+use crate::task::{exit_current_and_run_next, preempt_current_and_run_next, current_trap_cx, current_user_token, grow_current_stack, sample_load, scan_memwatch};
+use crate::timer::{get_time, set_next_trigger};
 use core::arch::{global_asm, asm};
+use core::sync::atomic::{AtomicBool, Ordering};
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
-    sie, stval, stvec,
+    sepc, sie, stval, stvec,
 };
 
 global_asm!(include_str!("trap.S"));
 
-/// initialize CSR `stvec` as the entry of `__alltraps`
+/// counter-enable bits for `cycle`, `time` and `instret`
+const COUNTEREN_CY_TM_IR: usize = 0b111;
+
+/// whether `cycle` and `instret` can be read below M mode, see
+/// [`probe_hw_counters`]
+static HW_COUNTERS: AtomicBool = AtomicBool::new(false);
+
+/// initialize CSR `stvec` as the entry of `__alltraps`, and let user mode
+/// read the `cycle`, `time` and `instret` counters
 pub fn init() {
     extern "C" {
         fn __alltraps();
     }
+    HW_COUNTERS.store(probe_hw_counters(), Ordering::Relaxed);
     unsafe {
         stvec::write(__alltraps as usize, TrapMode::Direct);
+        asm!("csrw scounteren, {}", in(reg) COUNTEREN_CY_TM_IR);
     }
 }
 
+/// whether user mode can read `cycle` and `instret`
+pub fn hw_counters() -> bool {
+    HW_COUNTERS.load(Ordering::Relaxed)
+}
+
+/// Read `cycle` and `instret` once with `__probe_trap` as the trap entry.
+///
+/// Unless the firmware sets them in `mcounteren` the reads trap, and the
+/// stub skips them and clears `a0`. `scounteren` only adds to what M mode
+/// allows, so the counters work in user mode iff they work here. Must run
+/// with interrupts disabled, before `stvec` is set up.
+fn probe_hw_counters() -> bool {
+    extern "C" {
+        fn __probe_trap();
+    }
+    let mut readable: usize = 1;
+    unsafe {
+        stvec::write(__probe_trap as usize, TrapMode::Direct);
+        asm!(
+            "csrr t1, cycle",
+            "csrr t1, instret",
+            inout("a0") readable,
+            out("t0") _,
+            out("t1") _,
+        );
+    }
+    readable != 0
+}
+
 /// timer interrupt enabled
 pub fn enable_timer_interrupt() {
     unsafe {
@@ -58,7 +101,12 @@ fn set_user_trap_entry() {
 
 #[no_mangle]
 pub fn trap_frome_kernel() -> ! {
-    panic!("a trap from kernel");
+    panic!(
+        "a trap from kernel: {:?} at {}, stval = {:#x}",
+        scause::read().cause(),
+        Symbol(sepc::read()),
+        stval::read()
+    );
 }
 
 #[no_mangle]
@@ -71,21 +119,34 @@ pub fn trap_handler() -> ! {
     match scause.cause() {
         Trap::Exception(Exception::UserEnvCall) => {
             cx.sepc += 4;
-            cx.x[10] = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]) as usize;
+            let args = [cx.x[10], cx.x[11], cx.x[12], cx.x[13], cx.x[14], cx.x[15]];
+            cx.x[10] = syscall(cx.x[17], args) as usize;
         }
-        Trap::Exception(Exception::StoreFault) | Trap::Exception(Exception::StorePageFault) => {
+        Trap::Exception(Exception::StorePageFault) | Trap::Exception(Exception::LoadPageFault)
+            if grow_current_stack(stval) => {}
+        Trap::Exception(Exception::StoreFault)
+        | Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::LoadFault)
+        | Trap::Exception(Exception::LoadPageFault) => {
             println!("[kernel] PageFault in application, bad addr = {:#x}, bad instruction = {:#x}, kernel killed it.", stval, cx.sepc);
             exit_current_and_run_next();
             // run_next_app();
         }
+        Trap::Exception(Exception::IllegalInstruction) if emulate_counter_read(cx) => {}
         Trap::Exception(Exception::IllegalInstruction) => {
             println!("[kernel] IllegalInstruction in application, kernel killed it.");
             exit_current_and_run_next();
             // run_next_app();
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            add_entropy(get_time() ^ cx.sepc);
+            vdso_update_tick(get_time());
+            sample_load();
+            scan_memwatch();
+            #[cfg(feature = "monitor")]
+            crate::monitor::poll();
             set_next_trigger();
-            suspend_current_and_run_next();
+            preempt_current_and_run_next();
         }
         _ => {
             panic!(
@@ -98,6 +159,37 @@ pub fn trap_handler() -> ! {
     trap_return();
 }
 
+/// Emulate `rdtime` for platforms where M mode does not delegate the
+/// counter to user mode.
+///
+/// Reading the counter in S mode would trap just the same, so the time is
+/// taken from [`get_time`], which the SBI serves from the memory-mapped
+/// timer. `rdcycle` and `rdinstret` have no such source and are treated as
+/// illegal instructions, user programs check [`hw_counters`] through
+/// `FEATURE_HW_COUNTERS` first.
+///
+/// Returns false if the faulting instruction is not `rdtime`.
+fn emulate_counter_read(cx: &mut TrapContext) -> bool {
+    let inst = match copy_from_user(current_user_token(), cx.sepc as *const u32) {
+        Ok(inst) => inst as usize,
+        Err(_) => return false,
+    };
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let rs1 = (inst >> 15) & 0x1f;
+    // only `csrrs rd, time, x0`, i.e. a plain read of `time`
+    if opcode != 0x73 || funct3 != 0b010 || rs1 != 0 || inst >> 20 != 0xc01 {
+        return false;
+    }
+    let value = get_time();
+    let rd = (inst >> 7) & 0x1f;
+    if rd != 0 {
+        cx.x[rd] = value;
+    }
+    cx.sepc += 4;
+    true
+}
+
 #[no_mangle]
 pub fn trap_return() -> ! {
     set_user_trap_entry();
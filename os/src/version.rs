@@ -0,0 +1,43 @@
+//! Version and build information of the kernel, see `sys_uname`
+//!
+//! The build id, build time and feature list are captured by `build.rs`.
+
+use syscall_abi::{uts_field, Utsname};
+
+const SYSNAME: &str = "rCore";
+const RELEASE: &str = env!("CARGO_PKG_VERSION");
+const BUILD_ID: &str = env!("KERNEL_BUILD_ID");
+/// seconds since the Unix epoch
+const BUILD_TIME: &str = env!("KERNEL_BUILD_TIME");
+const MACHINE: &str = "riscv64";
+const FEATURES: &str = env!("KERNEL_FEATURES");
+
+/// build time in seconds since the Unix epoch
+fn build_time() -> u64 {
+    BUILD_TIME.parse().unwrap_or(0)
+}
+
+/// the information returned by `sys_uname`
+pub fn uname() -> Utsname {
+    Utsname {
+        build_time: build_time(),
+        sysname: uts_field(SYSNAME),
+        release: uts_field(RELEASE),
+        build_id: uts_field(BUILD_ID),
+        machine: uts_field(MACHINE),
+        features: uts_field(FEATURES),
+    }
+}
+
+/// print the same information at boot, so that logs show what ran
+pub fn print_banner() {
+    println!(
+        "[kernel] {} {} ({}) {}, built at {}, features: [{}]",
+        SYSNAME,
+        RELEASE,
+        BUILD_ID,
+        MACHINE,
+        build_time(),
+        FEATURES
+    );
+}
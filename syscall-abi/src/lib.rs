@@ -0,0 +1,238 @@
+//! Syscall numbers, optional kernel features and the layouts of data shared
+//! with user space, used by both the kernel and the user library
+//!
+//! Every syscall is declared once in the [`syscalls!`] invocation below,
+//! which defines its `SYSCALL_*` number and its entry in [`SYSCALLS`]. The
+//! kernel builds its dispatch table in the same order as [`SYSCALLS`] and
+//! checks that at compile time, so a number changed or added here without a
+//! matching handler breaks the kernel build.
+
+#![no_std]
+
+/// a syscall known to both the kernel and the user library
+pub struct Syscall {
+    /// number passed in `a7`
+    pub id: usize,
+    /// name printed by strace
+    pub name: &'static str,
+    /// number of argument registers used, the rest should be zero
+    pub nargs: usize,
+    /// `FEATURE_*` bit the kernel must report for the syscall to exist, or 0
+    pub feature: usize,
+}
+
+/// the `frame_debug` feature is enabled
+pub const FEATURE_FRAME_DEBUG: usize = 1 << 0;
+/// `sys_getrandom` is available
+pub const FEATURE_GETRANDOM: usize = 1 << 1;
+/// `sys_get_memory_map` is available
+pub const FEATURE_MEMORY_MAP: usize = 1 << 2;
+/// `sys_loadavg` is available
+pub const FEATURE_LOADAVG: usize = 1 << 3;
+/// the vdso page is mapped at `VDSO`
+pub const FEATURE_VDSO: usize = 1 << 4;
+/// `sys_strace` is available
+pub const FEATURE_STRACE: usize = 1 << 5;
+/// `sys_shutdown` is available
+pub const FEATURE_SHUTDOWN: usize = 1 << 6;
+/// `sys_batch` is available
+pub const FEATURE_BATCH: usize = 1 << 7;
+/// the task information page is mapped at `PROC_INFO`
+pub const FEATURE_PROC_INFO: usize = 1 << 8;
+/// `sys_sysinfo` is available
+pub const FEATURE_SYSINFO: usize = 1 << 9;
+/// the `fault_inject` feature is enabled
+pub const FEATURE_FAULT_INJECT: usize = 1 << 10;
+/// `sys_get_io_stats` is available
+pub const FEATURE_IO_STATS: usize = 1 << 11;
+/// `sys_memwatch` is available
+pub const FEATURE_MEMWATCH: usize = 1 << 12;
+/// `sys_thread_cputime` and the CPU time clocks are available
+pub const FEATURE_THREAD_CPUTIME: usize = 1 << 13;
+/// `sys_getrusage` is available
+pub const FEATURE_RUSAGE: usize = 1 << 14;
+/// `sys_uname` is available
+pub const FEATURE_UNAME: usize = 1 << 15;
+/// the firmware lets user mode read the `cycle` and `instret` counters,
+/// which otherwise trap and kill the reader
+pub const FEATURE_HW_COUNTERS: usize = 1 << 16;
+
+macro_rules! syscalls {
+    ($($id:ident = $num:literal: $name:literal($nargs:literal) $(if $feature:ident)?;)*) => {
+        $(pub const $id: usize = $num;)*
+
+        /// all syscalls, sorted by number
+        pub const SYSCALLS: &[Syscall] = &[$(Syscall {
+            id: $id,
+            name: $name,
+            nargs: $nargs,
+            feature: 0 $(| $feature)?,
+        },)*];
+    };
+}
+
+syscalls! {
+    SYSCALL_IOCTL = 29: "ioctl"(3);
+    SYSCALL_WRITE = 64: "write"(3);
+    SYSCALL_EXIT = 93: "exit"(1);
+    SYSCALL_CLOCK_SETTIME = 112: "clock_settime"(2);
+    SYSCALL_CLOCK_GETTIME = 113: "clock_gettime"(2);
+    SYSCALL_CLOCK_GETRES = 114: "clock_getres"(2);
+    SYSCALL_YIELD = 124: "sched_yield"(0);
+    SYSCALL_UNAME = 160: "uname"(1) if FEATURE_UNAME;
+    SYSCALL_GETRUSAGE = 165: "getrusage"(2) if FEATURE_RUSAGE;
+    SYSCALL_GET_TIME = 169: "get_time"(0);
+    SYSCALL_GETPID = 172: "getpid"(0);
+    SYSCALL_GETRANDOM = 278: "getrandom"(2) if FEATURE_GETRANDOM;
+    SYSCALL_GET_MEMORY_MAP = 1100: "get_memory_map"(2) if FEATURE_MEMORY_MAP;
+    SYSCALL_LOADAVG = 1101: "loadavg"(1) if FEATURE_LOADAVG;
+    SYSCALL_KERNEL_FEATURES = 1102: "kernel_features"(0);
+    SYSCALL_STRACE = 1103: "strace"(2) if FEATURE_STRACE;
+    SYSCALL_SHUTDOWN = 1104: "shutdown"(1) if FEATURE_SHUTDOWN;
+    SYSCALL_BATCH = 1105: "batch"(2) if FEATURE_BATCH;
+    SYSCALL_SYSINFO = 1106: "sysinfo"(1) if FEATURE_SYSINFO;
+    SYSCALL_FAULT_INJECT = 1107: "fault_inject"(3) if FEATURE_FAULT_INJECT;
+    SYSCALL_GET_IO_STATS = 1108: "get_io_stats"(2) if FEATURE_IO_STATS;
+    SYSCALL_MEMWATCH = 1109: "memwatch"(1) if FEATURE_MEMWATCH;
+    SYSCALL_THREAD_CPUTIME = 1110: "thread_cputime"(1) if FEATURE_THREAD_CPUTIME;
+}
+
+/// at most this many argument registers are passed to a syscall
+pub const MAX_SYSCALL_ARGS: usize = 6;
+
+// numbers must be unique for dispatch and sorted for `lookup`
+const _: () = {
+    let mut i = 0;
+    while i < SYSCALLS.len() {
+        assert!(SYSCALLS[i].nargs <= MAX_SYSCALL_ARGS);
+        assert!(i == 0 || SYSCALLS[i - 1].id < SYSCALLS[i].id);
+        i += 1;
+    }
+};
+
+/// index of the syscall numbered `id` in [`SYSCALLS`]
+pub fn lookup(id: usize) -> Option<usize> {
+    SYSCALLS
+        .binary_search_by_key(&id, |syscall| syscall.id)
+        .ok()
+}
+
+/// length of the short string fields of [`Utsname`], including the NUL
+pub const UTS_LEN: usize = 65;
+/// length of [`Utsname::features`], including the NUL
+pub const UTS_FEATURES_LEN: usize = 257;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+/// Kernel name, version and build information filled by `sys_uname`.
+///
+/// The string fields are NUL-terminated and truncated if too long, see
+/// [`uts_field`] and [`uts_str`].
+pub struct Utsname {
+    /// build time in seconds since the Unix epoch
+    pub build_time: u64,
+    /// name of the kernel, "rCore"
+    pub sysname: [u8; UTS_LEN],
+    /// version of the kernel crate
+    pub release: [u8; UTS_LEN],
+    /// git hash of the kernel source with `-dirty` for uncommitted changes,
+    /// or "unknown"
+    pub build_id: [u8; UTS_LEN],
+    /// hardware the kernel runs on, "riscv64"
+    pub machine: [u8; UTS_LEN],
+    /// enabled cargo features of the kernel, separated by commas
+    pub features: [u8; UTS_FEATURES_LEN],
+}
+
+impl Default for Utsname {
+    fn default() -> Self {
+        Self {
+            build_time: 0,
+            sysname: [0; UTS_LEN],
+            release: [0; UTS_LEN],
+            build_id: [0; UTS_LEN],
+            machine: [0; UTS_LEN],
+            features: [0; UTS_FEATURES_LEN],
+        }
+    }
+}
+
+/// `s` as a NUL-terminated field of [`Utsname`], truncated to fit
+pub fn uts_field<const N: usize>(s: &str) -> [u8; N] {
+    let mut field = [0; N];
+    let len = s.len().min(N - 1);
+    field[..len].copy_from_slice(&s.as_bytes()[..len]);
+    field
+}
+
+/// the string in a field of [`Utsname`], up to the first NUL and without a
+/// character cut by truncation
+pub fn uts_str(field: &[u8]) -> &str {
+    let len = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    match core::str::from_utf8(&field[..len]) {
+        Ok(s) => s,
+        Err(err) => core::str::from_utf8(&field[..err.valid_up_to()]).unwrap(),
+    }
+}
+
+/// size of a page of the user address space
+pub const PAGE_SIZE: usize = 0x1000;
+/// read-only page shared by the kernel with every user address space, below
+/// the trampoline and the trap context
+pub const VDSO: usize = usize::MAX - 3 * PAGE_SIZE + 1;
+/// read-only page of per-task information, private to each user address space
+pub const PROC_INFO: usize = VDSO - PAGE_SIZE;
+/// "procinfo", marks an initialized [`ProcInfo`] page
+pub const PROC_INFO_MAGIC: usize = 0x6f66_6e69_636f_7270;
+
+#[repr(C)]
+/// layout of the page at [`VDSO`]
+pub struct VdsoData {
+    /// frequency of the `time` counter
+    pub clock_freq: usize,
+    /// `time` counter at the latest timer interrupt
+    pub last_tick: usize,
+}
+
+#[repr(C)]
+/// layout of the task information page at [`PROC_INFO`]
+pub struct ProcInfo {
+    /// [`PROC_INFO_MAGIC`]
+    pub magic: usize,
+    /// odd while the kernel updates the page, readers retry until they see
+    /// the same even value before and after reading
+    pub seq: usize,
+    /// id of the task
+    pub pid: usize,
+    /// frequency of the `time` counter
+    pub clock_freq: usize,
+}
+
+pub const AREA_PERM_R: usize = 1 << 1;
+pub const AREA_PERM_W: usize = 1 << 2;
+pub const AREA_PERM_X: usize = 1 << 3;
+pub const AREA_PERM_U: usize = 1 << 4;
+
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+/// map area information filled by `sys_get_memory_map`
+pub struct AreaInfo {
+    /// start virtual address
+    pub start: usize,
+    /// end virtual address (exclusive)
+    pub end: usize,
+    /// bits of `AREA_PERM_*`
+    pub perm: usize,
+    /// 0 for identical, 1 for framed
+    pub map_type: usize,
+    /// number of pages backed by physical frames
+    pub resident: usize,
+    /// pages accessed during the last scan interval, if the task enabled
+    /// `sys_memwatch`
+    pub touched: usize,
+    /// moving average of `touched`, scaled by 1000
+    pub hot: usize,
+}
@@ -0,0 +1,22 @@
+//! Pick the linker script of each user program
+
+use std::fs::read_dir;
+
+/// programs linked with their `.data` segment at an unaligned address
+static UNALIGNED_APPS: &[&str] = &["29unaligned_data"];
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/bin/");
+    println!("cargo:rerun-if-changed=src/linker.ld");
+    println!("cargo:rerun-if-changed=src/linker_unaligned.ld");
+    for dir_entry in read_dir("src/bin").unwrap() {
+        let name_with_ext = dir_entry.unwrap().file_name().into_string().unwrap();
+        let app = &name_with_ext[..name_with_ext.find('.').unwrap()];
+        let linker = if UNALIGNED_APPS.contains(&app) {
+            "src/linker_unaligned.ld"
+        } else {
+            "src/linker.ld"
+        };
+        println!("cargo:rustc-link-arg-bin={}=-T{}", app, linker);
+    }
+}
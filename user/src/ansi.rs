@@ -0,0 +1,39 @@
+//! ANSI escape sequences for full-screen programs
+
+/// foreground colors of [`set_color`]
+#[derive(Copy, Clone)]
+pub enum Color {
+    Black = 30,
+    Red = 31,
+    Green = 32,
+    Yellow = 33,
+    Blue = 34,
+    Magenta = 35,
+    Cyan = 36,
+    White = 37,
+}
+
+/// clear the screen and move the cursor to the top left corner
+pub fn clear_screen() {
+    print!("\x1b[2J\x1b[H");
+}
+
+/// clear from the cursor to the end of the line
+pub fn clear_line() {
+    print!("\x1b[K");
+}
+
+/// move the cursor to `row` and `col`, both starting from 1
+pub fn move_cursor(row: usize, col: usize) {
+    print!("\x1b[{};{}H", row, col);
+}
+
+/// set the foreground color of following output
+pub fn set_color(color: Color) {
+    print!("\x1b[{}m", color as usize);
+}
+
+/// reset colors and attributes
+pub fn reset_color() {
+    print!("\x1b[0m");
+}
@@ -0,0 +1,27 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::write;
+
+const PAGE_SIZE: usize = 0x1000;
+const STDOUT: usize = 1;
+const EFAULT: isize = 14;
+
+#[no_mangle]
+fn main() -> i32 {
+    extern "C" {
+        fn end_bss();
+    }
+    // the page right after .bss is not mapped (guard page below user stack)
+    let end = (end_bss as usize + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+    let partial = unsafe { core::slice::from_raw_parts((end - 8) as *const u8, 16) };
+    assert_eq!(write(STDOUT, partial), 8);
+    println!("");
+    let unmapped = unsafe { core::slice::from_raw_parts(0x1000 as *const u8, 16) };
+    assert_eq!(write(STDOUT, unmapped), -EFAULT);
+    println!("Test write_fault OK!");
+    0
+}
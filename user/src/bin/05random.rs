@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, getrandom, rand_u64, yield_};
+
+const BUF_SIZE: usize = 1024;
+const ROUNDS: usize = 1024;
+
+#[no_mangle]
+fn main() -> i32 {
+    // differs between boots, compare across runs
+    println!("random seed: {:#x}", rand_u64());
+    // streams requested at different times do not repeat each other
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+    assert_eq!(getrandom(&mut first), 32);
+    let start = get_time();
+    while get_time() - start < 20 {
+        yield_();
+    }
+    assert_eq!(getrandom(&mut second), 32);
+    assert_ne!(first, second);
+    let mut buf = [0u8; BUF_SIZE];
+    let mut ones = 0usize;
+    for _ in 0..ROUNDS {
+        assert_eq!(getrandom(&mut buf), BUF_SIZE as isize);
+        ones += buf.iter().map(|b| b.count_ones() as usize).sum::<usize>();
+    }
+    // monobit test over 1MiB: expect half of the bits set, within 1%
+    let bits = BUF_SIZE * ROUNDS * 8;
+    println!("{} ones in {} bits", ones, bits);
+    assert!(ones > bits / 2 - bits / 200 && ones < bits / 2 + bits / 200);
+    println!("Test random OK!");
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{cycles, instret, kernel_features, FEATURE_HW_COUNTERS};
+
+const ITER: u64 = 100000;
+
+fn work(iter: u64) -> u64 {
+    let mut acc = 0u64;
+    for i in 0..iter {
+        acc = core::hint::black_box(acc.wrapping_mul(31).wrapping_add(i));
+    }
+    acc
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    if kernel_features() & FEATURE_HW_COUNTERS == 0 {
+        println!("cycle and instret not readable, skipped");
+        return 0;
+    }
+    let (c0, i0) = (cycles(), instret());
+    work(ITER);
+    let (c1, i1) = (cycles(), instret());
+    work(ITER * 4);
+    let (c2, i2) = (cycles(), instret());
+    assert!(c0 < c1 && c1 < c2, "cycle counter is not monotonic");
+    assert!(i0 < i1 && i1 < i2, "instret counter is not monotonic");
+    let short = i1 - i0;
+    let long = i2 - i1;
+    println!("instructions per iteration: {}", short / ITER);
+    println!("cycles per iteration: {}", (c1 - c0) / ITER);
+    // four times the work should retire roughly four times the instructions
+    assert!(long > short * 3 && long < short * 5);
+    println!("Test counters OK!");
+    0
+}
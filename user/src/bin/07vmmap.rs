@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_memory_map, AreaInfo, AREA_PERM_R, AREA_PERM_U, AREA_PERM_W, AREA_PERM_X};
+
+const MAX_AREAS: usize = 16;
+
+fn flag(area: &AreaInfo, bit: usize, c: char) -> char {
+    if area.perm & bit != 0 {
+        c
+    } else {
+        '-'
+    }
+}
+
+fn find(areas: &[AreaInfo], addr: usize) -> Option<&AreaInfo> {
+    areas.iter().find(|a| a.start <= addr && addr < a.end)
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut areas = [AreaInfo::default(); MAX_AREAS];
+    let total = get_memory_map(&mut areas) as usize;
+    assert!(total > 0 && total <= MAX_AREAS);
+    println!("start              end                perm type    pages");
+    for area in &areas[..total] {
+        let map_type = if area.map_type == 0 {
+            "ident "
+        } else {
+            "framed"
+        };
+        println!(
+            "{:#018x} {:#018x} {}{}{}{} {} {}",
+            area.start,
+            area.end,
+            flag(area, AREA_PERM_R, 'r'),
+            flag(area, AREA_PERM_W, 'w'),
+            flag(area, AREA_PERM_X, 'x'),
+            flag(area, AREA_PERM_U, 'u'),
+            map_type,
+            area.resident,
+        );
+    }
+    // the stack and the code of this program are both described
+    let local = 0u8;
+    let stack = find(&areas[..total], &local as *const u8 as usize).unwrap();
+    let rwu = AREA_PERM_R | AREA_PERM_W | AREA_PERM_U;
+    assert_eq!(stack.perm & rwu, rwu);
+    let text = find(&areas[..total], main as usize).unwrap();
+    assert!(text.perm & AREA_PERM_X != 0);
+    // a short buffer still reports the total count
+    let mut one = [AreaInfo::default(); 1];
+    assert_eq!(get_memory_map(&mut one) as usize, total);
+    assert_eq!(one[0].start, areas[0].start);
+    println!("Test vmmap OK!");
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, loadavg, LoadAvg};
+
+fn print_load(load: &LoadAvg) {
+    let ms = get_time() as u64;
+    println!(
+        "up {}.{:03}s, {} processes, {} threads, load average: {}.{:03} {}.{:03} {}.{:03}",
+        ms / 1000,
+        ms % 1000,
+        load.processes,
+        load.threads,
+        load.loads[0] / 1000,
+        load.loads[0] % 1000,
+        load.loads[1] / 1000,
+        load.loads[1] % 1000,
+        load.loads[2] / 1000,
+        load.loads[2] % 1000,
+    );
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut load = LoadAvg::default();
+    assert_eq!(loadavg(&mut load), 0);
+    print_load(&load);
+    // stay runnable for two seconds, the 1s average must follow
+    let wait_for = get_time() + 2000;
+    while get_time() < wait_for {}
+    assert_eq!(loadavg(&mut load), 0);
+    print_load(&load);
+    assert!(load.processes >= 1 && load.threads >= load.processes);
+    assert!(load.loads[0] > 500);
+    println!("Test uptime OK!");
+    0
+}
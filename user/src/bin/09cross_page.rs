@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use core::ptr::addr_of_mut;
+use user_lib::{loadavg, LoadAvg};
+
+const PAGE_SIZE: usize = 0x1000;
+
+#[repr(C, align(4096))]
+struct TwoPages([u8; PAGE_SIZE * 2]);
+
+static mut PAGES: TwoPages = TwoPages([0xff; PAGE_SIZE * 2]);
+
+#[no_mangle]
+fn main() -> i32 {
+    // place the struct so that it straddles the boundary of the two pages
+    let offset = PAGE_SIZE - 16;
+    let base = unsafe { addr_of_mut!(PAGES) } as *mut u8;
+    let ptr = unsafe { base.add(offset) } as *mut LoadAvg;
+    assert_eq!(loadavg(unsafe { &mut *ptr }), 0);
+    let load = unsafe { ptr.read() };
+    println!("processes = {}, threads = {}", load.processes, load.threads);
+    assert!(load.processes >= 1 && load.processes <= 16);
+    assert_eq!(load.processes, load.threads);
+    // bytes around the struct are untouched
+    unsafe {
+        assert_eq!(base.add(offset - 1).read(), 0xff);
+        assert_eq!(base.add(offset + size_of::<LoadAvg>()).read(), 0xff);
+    }
+    println!("Test cross_page OK!");
+    0
+}
@@ -0,0 +1,22 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{kernel_features, raw_syscall, ENOSYS, FEATURE_GETRANDOM};
+
+const SYSCALL_NONEXISTENT: usize = 9999;
+
+#[no_mangle]
+fn main() -> i32 {
+    // the kernel should warn about this only once
+    for _ in 0..100 {
+        assert_eq!(raw_syscall(SYSCALL_NONEXISTENT, [1, 2, 3]), -ENOSYS);
+    }
+    let features = kernel_features();
+    println!("kernel features: {:#x}", features);
+    assert!(features & FEATURE_GETRANDOM != 0);
+    println!("Test enosys OK!");
+    0
+}
@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::ansi::{clear_line, clear_screen, move_cursor, reset_color, set_color, Color};
+use user_lib::{get_time, get_winsize, loadavg, set_winsize, yield_, LoadAvg, WinSize};
+
+const STDOUT: usize = 1;
+const FRAMES: usize = 3;
+
+fn repaint(winsize: &WinSize, load: &LoadAvg) {
+    clear_screen();
+    set_color(Color::Green);
+    print!("top - {} ms", get_time());
+    reset_color();
+    let lines = [
+        ("load 1s ", load.loads[0]),
+        ("load 10s", load.loads[1]),
+        ("load 60s", load.loads[2]),
+        ("procs   ", load.processes * 1000),
+    ];
+    // stay inside the window, the first row is the title
+    for (i, (name, value)) in lines.iter().enumerate() {
+        let row = i + 2;
+        if row > winsize.rows as usize {
+            break;
+        }
+        move_cursor(row, 1);
+        clear_line();
+        let room = (winsize.cols as usize).saturating_sub(name.len() + 1);
+        let bar = (*value as usize / 250).min(room);
+        print!("{} ", name);
+        for _ in 0..bar {
+            print!("#");
+        }
+    }
+    move_cursor(winsize.rows as usize, 1);
+    println!("");
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut winsize = WinSize::default();
+    assert_eq!(get_winsize(STDOUT, &mut winsize), 0);
+    let mut load = LoadAvg::default();
+    for _ in 0..FRAMES {
+        loadavg(&mut load);
+        repaint(&winsize, &load);
+        yield_();
+    }
+    // shrink the window and repaint within the new bounds
+    let small = WinSize {
+        rows: 4,
+        cols: 40,
+        ..winsize
+    };
+    assert_eq!(set_winsize(STDOUT, &small), 0);
+    let mut changed = WinSize::default();
+    assert_eq!(get_winsize(STDOUT, &mut changed), 0);
+    assert_eq!((changed.rows, changed.cols), (4, 40));
+    repaint(&changed, &load);
+    assert_eq!(set_winsize(STDOUT, &winsize), 0);
+    println!("Test top OK!");
+    0
+}
@@ -0,0 +1,29 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::date::DateTime;
+use user_lib::{clock_gettime, TimeSpec, CLOCK_REALTIME};
+
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(
+        DateTime::from_epoch_secs(951782400),
+        DateTime {
+            year: 2000,
+            month: 2,
+            day: 29,
+            hour: 0,
+            minute: 0,
+            second: 0
+        }
+    );
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_REALTIME, &mut ts), 0);
+    // compare with `date -u` on the host
+    println!("{}", DateTime::from_epoch_secs(ts.sec));
+    println!("Test date OK!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{cycles, get_time, get_time_fast};
+
+const ITER: usize = 100000;
+
+#[no_mangle]
+fn main() -> i32 {
+    let start = cycles();
+    for _ in 0..ITER {
+        get_time();
+    }
+    let syscall_cycles = cycles() - start;
+    let start = cycles();
+    for _ in 0..ITER {
+        get_time_fast();
+    }
+    let fast_cycles = cycles() - start;
+    println!(
+        "get_time: {} cycles/call, get_time_fast: {} cycles/call",
+        syscall_cycles / ITER as u64,
+        fast_cycles / ITER as u64
+    );
+    // both read the same clock, so the fast path is sandwiched by syscalls
+    for _ in 0..100 {
+        let before = get_time();
+        let fast = get_time_fast();
+        let after = get_time();
+        assert!(before <= fast && fast <= after);
+    }
+    println!("Test fast_time OK!");
+    0
+}
@@ -0,0 +1,23 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, getpid, strace, write, EPERM, ESRCH};
+
+#[no_mangle]
+fn main() -> i32 {
+    let pid = getpid() as usize;
+    assert_eq!(strace(usize::MAX, true), -ESRCH);
+    // there is always another task (e.g. task 0 when we are not task 0)
+    let other = if pid == 0 { 1 } else { 0 };
+    assert_eq!(strace(other, true), -EPERM);
+    // the kernel log should show strace, write, get_time and strace
+    assert_eq!(strace(pid, true), 0);
+    write(1, b"traced write\n");
+    get_time();
+    assert_eq!(strace(pid, false), 0);
+    println!("Test strace OK!");
+    0
+}
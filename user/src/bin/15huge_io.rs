@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use syscall_abi::SYSCALL_WRITE;
+use user_lib::raw_syscall;
+
+const PAGE_SIZE: usize = 0x1000;
+/// bytes written from the end of the last mapped page, which is zero past
+/// the end of .bss
+const TAIL: usize = 16;
+
+#[no_mangle]
+fn main() -> i32 {
+    extern "C" {
+        fn end_bss();
+    }
+    // the page right after .bss is not mapped (guard page below user stack),
+    // so a write starting just before it stops there
+    let end = (end_bss as usize + PAGE_SIZE - 1) & !(PAGE_SIZE - 1);
+    // each write is short instead of exhausting the kernel heap
+    for _ in 0..100 {
+        let written = raw_syscall(SYSCALL_WRITE, [1, end - TAIL, 1 << 30]);
+        assert_eq!(written, TAIL as isize);
+    }
+    println!("");
+    println!("Test huge_io OK!");
+    0
+}
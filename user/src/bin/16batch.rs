@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use syscall_abi::{SYSCALL_EXIT, SYSCALL_GET_TIME, SYSCALL_WRITE};
+use user_lib::{batch, BatchEntry, BATCH_MAX, BATCH_STOP_ON_ERROR, EFAULT, EINVAL};
+
+const LINES: [&str; 10] = [
+    "batch 0\n",
+    "batch 1\n",
+    "batch 2\n",
+    "batch 3\n",
+    "batch 4\n",
+    "batch 5\n",
+    "batch 6\n",
+    "batch 7\n",
+    "batch 8\n",
+    "batch 9\n",
+];
+
+fn write_entry(s: &str) -> BatchEntry {
+    BatchEntry::new(SYSCALL_WRITE, [1, s.as_ptr() as usize, s.len()])
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    // ten writes with a single trap, in order
+    let mut entries = LINES.map(write_entry);
+    assert_eq!(batch(&mut entries), 10);
+    for (entry, line) in entries.iter().zip(LINES) {
+        assert_eq!(entry.ret, line.len() as isize);
+    }
+    // exit is refused, and a failing entry marked stop-on-error ends the batch
+    let mut entries = [
+        BatchEntry::new(SYSCALL_EXIT, [1, 0, 0]),
+        BatchEntry::new(SYSCALL_GET_TIME, [0, 0, 0]),
+        BatchEntry {
+            flags: BATCH_STOP_ON_ERROR,
+            ..BatchEntry::new(SYSCALL_WRITE, [1, 0, 8])
+        },
+        write_entry("not reached\n"),
+    ];
+    assert_eq!(batch(&mut entries), 3);
+    assert_eq!(entries[0].ret, -EINVAL);
+    assert!(entries[1].ret >= 0);
+    assert_eq!(entries[2].ret, -EFAULT);
+    assert_eq!(entries[3].ret, 0);
+    // too many entries
+    let mut entries = [BatchEntry::new(SYSCALL_GET_TIME, [0, 0, 0]); BATCH_MAX + 1];
+    assert_eq!(batch(&mut entries), -EINVAL);
+    println!("Test batch OK!");
+    0
+}
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{cycles, getpid, getpid_syscall};
+
+const ITER: usize = 1_000_000;
+
+#[no_mangle]
+fn main() -> i32 {
+    let pid = getpid_syscall();
+    assert_eq!(getpid(), pid);
+    let start = cycles();
+    for _ in 0..ITER {
+        getpid_syscall();
+    }
+    let syscall_cycles = cycles() - start;
+    let start = cycles();
+    for _ in 0..ITER {
+        assert_eq!(getpid(), pid);
+    }
+    let page_cycles = cycles() - start;
+    println!(
+        "getpid: {} cycles/call via syscall, {} cycles/call via the info page",
+        syscall_cycles / ITER as u64,
+        page_cycles / ITER as u64
+    );
+    println!("Test getpid OK!");
+    0
+}
@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{sysinfo, SysInfo};
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    println!(
+        "up {} ms, ram {} KiB free of {} KiB, kernel heap {} KiB used of {} KiB ({} KiB grown)",
+        info.uptime_ms,
+        info.free_ram / 1024,
+        info.total_ram / 1024,
+        info.heap_used / 1024,
+        info.heap_total / 1024,
+        info.heap_grown / 1024
+    );
+    println!(
+        "timer programmed via {}",
+        if info.timer_sstc != 0 {
+            "stimecmp"
+        } else {
+            "SBI"
+        }
+    );
+    assert!(info.free_ram <= info.total_ram);
+    assert!(info.heap_used <= info.heap_total);
+    assert!(info.heap_grown <= info.heap_total);
+    println!("Test sysinfo OK!");
+    0
+}
@@ -0,0 +1,50 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::cycles;
+use user_lib::sync::{backoff, spin_loop_hint, SpinBarrier};
+
+const ITER: usize = 100000;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static BARRIER: SpinBarrier = SpinBarrier::new(1);
+
+#[no_mangle]
+fn main() -> i32 {
+    // the hint must not trap, whether or not the CPU has Zihintpause
+    let start = cycles();
+    for _ in 0..ITER {
+        spin_loop_hint();
+    }
+    println!("pause: {} cycles", (cycles() - start) / ITER as u64);
+    // a compare-exchange loop, backing off on contention
+    for _ in 0..ITER {
+        let mut iteration = 0;
+        loop {
+            let value = COUNTER.load(Ordering::Relaxed);
+            if COUNTER
+                .compare_exchange_weak(value, value + 1, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                break;
+            }
+            backoff(iteration);
+            iteration += 1;
+        }
+    }
+    assert_eq!(COUNTER.load(Ordering::Relaxed), ITER);
+    // the long backoffs yield instead of spinning
+    for iteration in 0..16 {
+        backoff(iteration);
+    }
+    // a single participant never waits
+    for _ in 0..100 {
+        assert!(BARRIER.wait());
+    }
+    println!("Test spin OK!");
+    0
+}
@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use syscall_abi::{SYSCALL_FAULT_INJECT, SYSCALL_GET_MEMORY_MAP, SYSCALL_GET_TIME};
+use user_lib::{
+    batch, fault_inject, get_memory_map, sysinfo, AreaInfo, BatchEntry, SysInfo, ENOMEM, ENOSYS,
+    FAULT_FRAME_ALLOC, FAULT_HEAP_ALLOC, FAULT_MODE_EVERY_NTH, FAULT_MODE_NEXT,
+};
+
+#[no_mangle]
+fn main() -> i32 {
+    if fault_inject(FAULT_HEAP_ALLOC, FAULT_MODE_NEXT, 0) == -ENOSYS {
+        println!("kernel built without fault_inject, skipped");
+        return 0;
+    }
+    let mut entries = [BatchEntry::new(SYSCALL_GET_TIME, [0, 0, 0]); 10];
+    // the batch cannot allocate its copy of the entries
+    assert_eq!(fault_inject(FAULT_HEAP_ALLOC, FAULT_MODE_NEXT, 1), 0);
+    assert_eq!(batch(&mut entries), -ENOMEM);
+    // the failure was used up
+    assert_eq!(batch(&mut entries), 10);
+    // every allocation fails, then injection is stopped
+    assert_eq!(fault_inject(FAULT_HEAP_ALLOC, FAULT_MODE_EVERY_NTH, 1), 0);
+    for _ in 0..10 {
+        assert_eq!(batch(&mut entries), -ENOMEM);
+    }
+    assert_eq!(fault_inject(FAULT_HEAP_ALLOC, FAULT_MODE_NEXT, 0), 0);
+    assert_eq!(batch(&mut entries), 10);
+    // syscalls without frame allocations are unaffected by frame failures
+    assert_eq!(fault_inject(FAULT_FRAME_ALLOC, FAULT_MODE_EVERY_NTH, 1), 0);
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    assert_eq!(fault_inject(FAULT_FRAME_ALLOC, FAULT_MODE_NEXT, 0), 0);
+    // an injection armed inside a batch hits the next entry of this task
+    // only, though other tasks may run between the entries
+    let mut areas = [AreaInfo::default(); 32];
+    let map = BatchEntry::new(
+        SYSCALL_GET_MEMORY_MAP,
+        [areas.as_mut_ptr() as usize, areas.len(), 0],
+    );
+    let mut entries = [
+        BatchEntry::new(SYSCALL_FAULT_INJECT, [FAULT_HEAP_ALLOC, FAULT_MODE_NEXT, 1]),
+        map,
+        map,
+    ];
+    assert_eq!(batch(&mut entries), 3);
+    assert_eq!(entries[0].ret, 0);
+    assert_eq!(entries[1].ret, -ENOMEM);
+    assert!(entries[2].ret > 0);
+    assert_eq!(get_memory_map(&mut areas), entries[2].ret);
+    println!("Test fault_inject OK!");
+    0
+}
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_io_stats, getpid, write, IoStats, EPERM, ESRCH};
+
+const STDOUT: usize = 1;
+const LINES: usize = 16;
+const LINE_LEN: usize = 64;
+
+#[no_mangle]
+fn main() -> i32 {
+    let pid = getpid() as usize;
+    let mut before = IoStats::default();
+    assert_eq!(get_io_stats(pid, &mut before), 0);
+    let mut line = [b'.'; LINE_LEN];
+    line[LINE_LEN - 1] = b'\n';
+    for _ in 0..LINES {
+        assert_eq!(write(STDOUT, &line), LINE_LEN as isize);
+    }
+    let mut after = IoStats::default();
+    assert_eq!(get_io_stats(pid, &mut after), 0);
+    assert_eq!(
+        after.console_write_bytes - before.console_write_bytes,
+        (LINES * LINE_LEN) as u64
+    );
+    // the writes and the second query
+    assert_eq!(after.syscalls - before.syscalls, LINES as u64 + 1);
+    println!(
+        "{} bytes written in {} syscalls",
+        after.console_write_bytes, after.syscalls
+    );
+    let mut other = IoStats::default();
+    assert_eq!(
+        get_io_stats(if pid == 0 { 1 } else { 0 }, &mut other),
+        -EPERM
+    );
+    assert_eq!(get_io_stats(usize::MAX, &mut other), -ESRCH);
+    println!("Test io_stats OK!");
+    0
+}
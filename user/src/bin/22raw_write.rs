@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::write;
+
+const STDOUT: usize = 1;
+const PAGE_SIZE: usize = 0x1000;
+const CRABS: &str = "crabs 🦀🦀🦀 cross ✨ the page 🦀 boundary";
+
+#[repr(C, align(4096))]
+struct Pages([u8; 2 * PAGE_SIZE]);
+
+static mut PAGES: Pages = Pages([0; 2 * PAGE_SIZE]);
+
+#[no_mangle]
+fn main() -> i32 {
+    // invalid UTF-8, avoiding ESC and C1 controls which may confuse the
+    // terminal
+    let mut garbage = [0u8; 0x61];
+    for (i, byte) in garbage[..0x60].iter_mut().enumerate() {
+        *byte = 0xa0 + i as u8;
+    }
+    garbage[0x60] = b'\n';
+    assert_eq!(write(STDOUT, &garbage), garbage.len() as isize);
+    let controls = b"nul \0 and bell \x07 pass through\n";
+    assert_eq!(write(STDOUT, controls), controls.len() as isize);
+    // the first crab straddles the page boundary
+    let start = PAGE_SIZE - 8;
+    let pages = unsafe { &mut *core::ptr::addr_of_mut!(PAGES.0) };
+    let text = &mut pages[start..start + CRABS.len() + 1];
+    text[..CRABS.len()].copy_from_slice(CRABS.as_bytes());
+    text[CRABS.len()] = b'\n';
+    assert!(!CRABS.is_char_boundary(PAGE_SIZE - start));
+    assert_eq!(write(STDOUT, text), text.len() as isize);
+    println!("{}", CRABS);
+    println!("Test raw_write OK!");
+    0
+}
@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_memory_map, get_time, memwatch, AreaInfo};
+
+const MAX_AREAS: usize = 16;
+const PAGE_SIZE: usize = 0x1000;
+const PAGES: usize = 16;
+/// a few scan intervals of the kernel
+const PHASE_MS: isize = 1000;
+
+static mut BUFFER: [u8; PAGES * PAGE_SIZE] = [0; PAGES * PAGE_SIZE];
+
+/// the area containing the buffer
+fn buffer_area() -> AreaInfo {
+    let mut areas = [AreaInfo::default(); MAX_AREAS];
+    let total = (get_memory_map(&mut areas) as usize).min(MAX_AREAS);
+    let addr = unsafe { core::ptr::addr_of!(BUFFER) as usize };
+    *areas[..total]
+        .iter()
+        .find(|a| a.start <= addr && addr < a.end)
+        .unwrap()
+}
+
+/// run for a while, touching every page of the buffer if `touch`
+fn phase(touch: bool) -> AreaInfo {
+    let end = get_time() + PHASE_MS;
+    let buffer = unsafe { core::ptr::addr_of_mut!(BUFFER) as *mut u8 };
+    while get_time() < end {
+        if touch {
+            for page in 0..PAGES {
+                unsafe { buffer.add(page * PAGE_SIZE).write_volatile(page as u8) };
+            }
+        }
+    }
+    let area = buffer_area();
+    println!(
+        "touch {}: {} of {} pages touched, hot {}.{:03}",
+        touch,
+        area.touched,
+        (area.end - area.start) / PAGE_SIZE,
+        area.hot / 1000,
+        area.hot % 1000
+    );
+    area
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    assert_eq!(memwatch(true), 0);
+    let busy = phase(true);
+    assert!(busy.touched >= PAGES);
+    // only the few pages of other statics may still be touched
+    let idle = phase(false);
+    assert!(idle.touched < PAGES / 2);
+    assert!(idle.hot < busy.hot);
+    let busy = phase(true);
+    assert!(busy.touched >= PAGES);
+    assert_eq!(memwatch(false), 0);
+    println!("Test memwatch OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    clock_gettime, get_time, thread_cputime, TimeSpec, CLOCK_PROCESS_CPUTIME_ID,
+    CLOCK_THREAD_CPUTIME_ID, ESRCH,
+};
+
+const SPIN_MS: isize = 300;
+/// a timer tick, by which the samples below may be apart
+const SLACK_US: isize = 10_000;
+
+fn clock_us(clock_id: usize) -> isize {
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(clock_id, &mut ts), 0);
+    (ts.sec * 1_000_000 + ts.nsec / 1000) as isize
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let wall_start = get_time();
+    let start = thread_cputime(0);
+    assert!(start >= 0);
+    while get_time() < wall_start + SPIN_MS {}
+    let end = thread_cputime(0);
+    let wall = (get_time() - wall_start) * 1000;
+    let spent = end - start;
+    println!("spun {} us of CPU time in {} us", spent, wall);
+    // other tasks may have run in between, but never this one twice
+    assert!(spent > 0);
+    assert!(spent <= wall + SLACK_US);
+    // with a single thread, all clocks agree
+    let process = clock_us(CLOCK_PROCESS_CPUTIME_ID);
+    let thread = clock_us(CLOCK_THREAD_CPUTIME_ID);
+    let now = thread_cputime(0);
+    assert!(end <= process && process <= thread && thread <= now);
+    assert!(now - end < SLACK_US);
+    assert_eq!(thread_cputime(1), -ESRCH);
+    println!("Test cputime OK!");
+    0
+}
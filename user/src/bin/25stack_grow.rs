@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_memory_map, AreaInfo};
+
+const MAX_AREAS: usize = 16;
+const FRAME_SIZE: usize = 1024;
+/// about 600KiB of stack
+const DEPTH: usize = 600;
+
+fn stack_area() -> AreaInfo {
+    let mut areas = [AreaInfo::default(); MAX_AREAS];
+    let total = (get_memory_map(&mut areas) as usize).min(MAX_AREAS);
+    let local = 0u8;
+    let sp = &local as *const u8 as usize;
+    *areas[..total]
+        .iter()
+        .find(|a| a.start <= sp && sp < a.end)
+        .unwrap()
+}
+
+/// use `FRAME_SIZE` bytes of stack per call, returning a checksum of them
+#[inline(never)]
+fn recurse(depth: usize) -> usize {
+    let mut frame = [0u8; FRAME_SIZE];
+    for (i, byte) in frame.iter_mut().enumerate() {
+        unsafe { core::ptr::write_volatile(byte, (i + depth) as u8) };
+    }
+    let below = if depth == 0 { 0 } else { recurse(depth - 1) };
+    let sum: usize = frame
+        .iter()
+        .map(|byte| unsafe { core::ptr::read_volatile(byte) } as usize)
+        .sum();
+    below + sum
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let before = stack_area();
+    let expected: usize = (0..=DEPTH)
+        .map(|depth| {
+            (0..FRAME_SIZE)
+                .map(|i| (i + depth) as u8 as usize)
+                .sum::<usize>()
+        })
+        .sum();
+    assert_eq!(recurse(DEPTH), expected);
+    let after = stack_area();
+    println!(
+        "stack grew from {} KiB to {} KiB",
+        (before.end - before.start) / 1024,
+        (after.end - after.start) / 1024
+    );
+    assert_eq!(after.end, before.end);
+    assert!(after.end - after.start >= DEPTH * FRAME_SIZE);
+    println!("Test stack_grow OK!");
+    0
+}
@@ -0,0 +1,19 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/// inside the region the stack may grow into, but too far below the stack to
+/// be a push
+const WILD_OFFSET: usize = 512 << 10;
+
+#[no_mangle]
+fn main() -> i32 {
+    let local = 0u8;
+    let wild = (&local as *const u8 as usize - WILD_OFFSET) as *mut u8;
+    println!("Test stack_wild: the kernel should kill this program");
+    unsafe { wild.write_volatile(1) };
+    println!("Test stack_wild FAILED: wild write did not fault");
+    0
+}
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use syscall_abi::SYSCALL_IOCTL;
+use user_lib::{get_winsize, raw_syscall, write, WinSize, EBADF, EFAULT};
+
+const TIOCSWINSZ: usize = 0x5414;
+const STDIN: usize = 0;
+const STDOUT: usize = 1;
+
+#[no_mangle]
+fn main() -> i32 {
+    // unknown descriptors and stdin are rejected instead of stopping the kernel
+    assert_eq!(write(5, b"lost\n"), -EBADF);
+    assert_eq!(write(usize::MAX, b"lost\n"), -EBADF);
+    assert_eq!(write(STDIN, b"lost\n"), -EBADF);
+    let mut winsize = WinSize::default();
+    assert_eq!(get_winsize(5, &mut winsize), -EBADF);
+    assert_eq!(get_winsize(STDOUT, &mut winsize), 0);
+    // the kernel half is never accessible from user mode
+    let bad = usize::MAX - 7;
+    assert_eq!(
+        raw_syscall(SYSCALL_IOCTL, [STDOUT, TIOCSWINSZ, bad]),
+        -EFAULT
+    );
+    println!("Test bad_fd OK!");
+    0
+}
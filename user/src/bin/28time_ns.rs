@@ -0,0 +1,45 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, get_time, yield_, TimeSpec, CLOCK_MONOTONIC};
+
+const NSEC_PER_SEC: u64 = 1_000_000_000;
+const NSEC_PER_MSEC: u64 = 1_000_000;
+
+fn monotonic_ns() -> u64 {
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut ts), 0);
+    assert!((ts.nsec as u64) < NSEC_PER_SEC);
+    ts.sec as u64 * NSEC_PER_SEC + ts.nsec as u64
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    // a syscall takes far longer than one tick of the time counter
+    let mut min_step = u64::MAX;
+    let mut last = monotonic_ns();
+    for _ in 0..1000 {
+        let now = monotonic_ns();
+        assert!(now > last);
+        min_step = min_step.min(now - last);
+        last = now;
+    }
+    assert!(min_step < NSEC_PER_MSEC);
+    // wait until 10ms have passed on the millisecond clock
+    let start_ms = get_time();
+    while get_time() == start_ms {}
+    let start = monotonic_ns();
+    let deadline = get_time() + 10;
+    while get_time() < deadline {
+        yield_();
+    }
+    let elapsed = monotonic_ns() - start;
+    println!("10ms measured as {}ns, min step {}ns", elapsed, min_step);
+    // other tasks may run meanwhile, so only the lower bound is exact
+    assert!(elapsed >= 9 * NSEC_PER_MSEC);
+    println!("Test time_ns OK!");
+    0
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{addr_of, addr_of_mut};
+
+const PAGE_SIZE: usize = 0x1000;
+
+// linked by linker_unaligned.ld, so .data starts 0x720 bytes into a page
+static mut PATTERN: [u32; 8] = [
+    0x0123_4567,
+    0x89ab_cdef,
+    0xdead_beef,
+    0xcafe_babe,
+    0x1357_9bdf,
+    0x2468_ace0,
+    0x0f1e_2d3c,
+    0x4b5a_6978,
+];
+static mut COUNTER: usize = 42;
+
+#[no_mangle]
+fn main() -> i32 {
+    let pattern = unsafe { addr_of!(PATTERN).read_volatile() };
+    let counter = unsafe { addr_of!(COUNTER).read_volatile() };
+    println!(
+        "PATTERN at {:#x} (page offset {:#x})",
+        addr_of!(PATTERN) as usize,
+        addr_of!(PATTERN) as usize % PAGE_SIZE
+    );
+    assert_eq!(
+        pattern,
+        [
+            0x0123_4567,
+            0x89ab_cdef,
+            0xdead_beef,
+            0xcafe_babe,
+            0x1357_9bdf,
+            0x2468_ace0,
+            0x0f1e_2d3c,
+            0x4b5a_6978,
+        ]
+    );
+    assert_eq!(counter, 42);
+    // the segment is writable as well
+    unsafe {
+        addr_of_mut!(COUNTER).write_volatile(counter + 1);
+        assert_eq!(addr_of!(COUNTER).read_volatile(), 43);
+    }
+    println!("Test unaligned_data OK!");
+    0
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getrusage, yield_, RUsage, EINVAL, ENOSYS, RUSAGE_CHILDREN, RUSAGE_SELF};
+
+const PAGE_SIZE: usize = 0x1000;
+/// pages of stack touched beyond the initial user stack
+const PAGES: usize = 64;
+
+fn rusage() -> RUsage {
+    let mut usage = RUsage::default();
+    assert_eq!(getrusage(RUSAGE_SELF, &mut usage), 0);
+    usage
+}
+
+/// touch `pages` pages of stack below the caller, one fault each
+#[inline(never)]
+fn touch_stack(pages: usize) -> u8 {
+    let mut page = [0u8; PAGE_SIZE];
+    unsafe { core::ptr::write_volatile(&mut page[0], pages as u8) };
+    let below = if pages == 0 {
+        0
+    } else {
+        touch_stack(pages - 1)
+    };
+    below.wrapping_add(unsafe { core::ptr::read_volatile(&page[0]) })
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut usage = RUsage::default();
+    if getrusage(RUSAGE_SELF, &mut usage) == -ENOSYS {
+        println!("getrusage not supported, skipped");
+        return 0;
+    }
+    assert_eq!(getrusage(-2, &mut usage), -EINVAL);
+    assert_eq!(getrusage(RUSAGE_CHILDREN, &mut usage), 0);
+    assert_eq!(usage.maxrss_kb, 0);
+    let before = rusage();
+    for _ in 0..10 {
+        yield_();
+    }
+    touch_stack(PAGES);
+    let after = rusage();
+    println!(
+        "maxrss {} -> {} KiB, minflt {} -> {}, nvcsw {} -> {}, nivcsw {}",
+        before.maxrss_kb,
+        after.maxrss_kb,
+        before.minflt,
+        after.minflt,
+        before.nvcsw,
+        after.nvcsw,
+        after.nivcsw
+    );
+    assert!(after.nvcsw >= before.nvcsw + 10);
+    assert!(after.minflt > before.minflt);
+    assert!(after.maxrss_kb >= before.maxrss_kb + (PAGES * PAGE_SIZE / 1024) as u64 / 2);
+    assert_eq!(after.majflt, 0);
+    assert!(after.cpu_time_us >= before.cpu_time_us);
+    // the stack never shrinks, and the peak never drops anyway
+    touch_stack(1);
+    assert!(rusage().maxrss_kb >= after.maxrss_kb);
+    println!("Test rusage OK!");
+    0
+}
@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{uname, uts_str, Utsname, ENOSYS};
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut uts = Utsname::default();
+    match uname(&mut uts) {
+        0 => {}
+        ret if ret == -ENOSYS => {
+            println!("uname not supported, skipped");
+            return 0;
+        }
+        ret => panic!("uname failed with {}", ret),
+    }
+    let build_id = uts_str(&uts.build_id);
+    println!(
+        "{} {} ({}) {}, built at {}, features: [{}]",
+        uts_str(&uts.sysname),
+        uts_str(&uts.release),
+        build_id,
+        uts_str(&uts.machine),
+        uts.build_time,
+        uts_str(&uts.features)
+    );
+    assert_eq!(uts_str(&uts.sysname), "rCore");
+    assert_eq!(uts_str(&uts.machine), "riscv64");
+    assert!(!uts_str(&uts.release).is_empty());
+    // a git hash, optionally dirty, or "unknown" outside of a checkout
+    let hash = build_id.strip_suffix("-dirty").unwrap_or(build_id);
+    assert!(hash == "unknown" || (!hash.is_empty() && hash.bytes().all(|b| b.is_ascii_hexdigit())));
+    // every field is NUL-terminated
+    assert!(uts.sysname.contains(&0) && uts.features.contains(&0));
+    println!("Test uname OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, getpid, yield_};
+
+const TERMS: usize = 200_000;
+
+/// Leibniz series for pi/4, the accumulator lives in an FP register across
+/// yields and timer interrupts
+#[inline(never)]
+fn leibniz(terms: usize) -> f64 {
+    let mut sum = 0.0f64;
+    let mut sign = 1.0f64;
+    for k in 0..terms {
+        sum += sign / (2 * k + 1) as f64;
+        sign = -sign;
+        if k % 20_000 == 0 {
+            yield_();
+        }
+    }
+    sum * 4.0
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let start = get_time();
+    let pi = leibniz(TERMS);
+    // the error of the series is about 1 / terms
+    assert!((pi - core::f64::consts::PI).abs() < 1e-5);
+    // halving and doubling are exact
+    let half = getpid() as f64 * 0.5;
+    assert_eq!((half * 2.0) as isize, getpid());
+    println!(
+        "pi ~ {} from {} terms in {}ms",
+        pi,
+        TERMS,
+        get_time() - start
+    );
+    println!("Test float OK!");
+    0
+}
@@ -0,0 +1,152 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{get_time, getpid};
+
+/// spin this long with all FP registers live, across many task switches
+const SPIN_MS: usize = 300;
+/// round towards zero, not the default
+const FRM_RTZ: usize = 1;
+
+fn rdtime() -> usize {
+    let time: usize;
+    unsafe { asm!("rdtime {}", out(reg) time) };
+    time
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let seed = 0x3ff0_0000_0000_0000 | (getpid() as u64) << 32;
+    let mut src = [0u64; 32];
+    for (i, reg) in src.iter_mut().enumerate() {
+        *reg = seed | i as u64;
+    }
+    let mut dst = [0u64; 32];
+    // ticks of the time counter per 10ms
+    let start = get_time();
+    let ticks_start = rdtime();
+    while get_time() < start + 10 {}
+    let ticks_10ms = rdtime() - ticks_start;
+    let deadline = rdtime() + SPIN_MS / 10 * ticks_10ms;
+    let start = get_time();
+    let frm: usize;
+    unsafe {
+        asm!(
+            "fld f0, 0({src})",
+            "fld f1, 8({src})",
+            "fld f2, 16({src})",
+            "fld f3, 24({src})",
+            "fld f4, 32({src})",
+            "fld f5, 40({src})",
+            "fld f6, 48({src})",
+            "fld f7, 56({src})",
+            "fld f8, 64({src})",
+            "fld f9, 72({src})",
+            "fld f10, 80({src})",
+            "fld f11, 88({src})",
+            "fld f12, 96({src})",
+            "fld f13, 104({src})",
+            "fld f14, 112({src})",
+            "fld f15, 120({src})",
+            "fld f16, 128({src})",
+            "fld f17, 136({src})",
+            "fld f18, 144({src})",
+            "fld f19, 152({src})",
+            "fld f20, 160({src})",
+            "fld f21, 168({src})",
+            "fld f22, 176({src})",
+            "fld f23, 184({src})",
+            "fld f24, 192({src})",
+            "fld f25, 200({src})",
+            "fld f26, 208({src})",
+            "fld f27, 216({src})",
+            "fld f28, 224({src})",
+            "fld f29, 232({src})",
+            "fld f30, 240({src})",
+            "fld f31, 248({src})",
+            "fsrm {rm}",
+            "2:",
+            "rdtime {now}",
+            "bltu {now}, {deadline}, 2b",
+            "fsd f0, 0({dst})",
+            "fsd f1, 8({dst})",
+            "fsd f2, 16({dst})",
+            "fsd f3, 24({dst})",
+            "fsd f4, 32({dst})",
+            "fsd f5, 40({dst})",
+            "fsd f6, 48({dst})",
+            "fsd f7, 56({dst})",
+            "fsd f8, 64({dst})",
+            "fsd f9, 72({dst})",
+            "fsd f10, 80({dst})",
+            "fsd f11, 88({dst})",
+            "fsd f12, 96({dst})",
+            "fsd f13, 104({dst})",
+            "fsd f14, 112({dst})",
+            "fsd f15, 120({dst})",
+            "fsd f16, 128({dst})",
+            "fsd f17, 136({dst})",
+            "fsd f18, 144({dst})",
+            "fsd f19, 152({dst})",
+            "fsd f20, 160({dst})",
+            "fsd f21, 168({dst})",
+            "fsd f22, 176({dst})",
+            "fsd f23, 184({dst})",
+            "fsd f24, 192({dst})",
+            "fsd f25, 200({dst})",
+            "fsd f26, 208({dst})",
+            "fsd f27, 216({dst})",
+            "fsd f28, 224({dst})",
+            "fsd f29, 232({dst})",
+            "fsd f30, 240({dst})",
+            "fsd f31, 248({dst})",
+            "frrm {rm}",
+            src = in(reg) src.as_ptr(),
+            dst = in(reg) dst.as_mut_ptr(),
+            deadline = in(reg) deadline,
+            now = out(reg) _,
+            rm = inout(reg) FRM_RTZ => frm,
+            out("f0") _,
+            out("f1") _,
+            out("f2") _,
+            out("f3") _,
+            out("f4") _,
+            out("f5") _,
+            out("f6") _,
+            out("f7") _,
+            out("f8") _,
+            out("f9") _,
+            out("f10") _,
+            out("f11") _,
+            out("f12") _,
+            out("f13") _,
+            out("f14") _,
+            out("f15") _,
+            out("f16") _,
+            out("f17") _,
+            out("f18") _,
+            out("f19") _,
+            out("f20") _,
+            out("f21") _,
+            out("f22") _,
+            out("f23") _,
+            out("f24") _,
+            out("f25") _,
+            out("f26") _,
+            out("f27") _,
+            out("f28") _,
+            out("f29") _,
+            out("f30") _,
+            out("f31") _,
+        );
+    }
+    println!("FP registers kept for {}ms", get_time() - start);
+    assert_eq!(dst, src);
+    assert_eq!(frm, FRM_RTZ);
+    println!("Test float_regs OK!");
+    0
+}
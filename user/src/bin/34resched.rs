@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use syscall_abi::SYSCALL_GETRANDOM;
+use user_lib::{batch, get_time, getrusage, BatchEntry, RUsage, BATCH_MAX, ENOSYS, RUSAGE_SELF};
+
+/// bytes filled by one `getrandom`, the most the kernel does at once
+const CHUNK: usize = 0x1_0000;
+/// a single batch should take several time slices of 10ms
+const MIN_BATCH_MS: isize = 50;
+
+static mut BUF: [u8; CHUNK] = [0; CHUNK];
+
+fn involuntary_switches() -> u64 {
+    let mut usage = RUsage::default();
+    assert_eq!(getrusage(RUSAGE_SELF, &mut usage), 0);
+    usage.nivcsw
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let buf = unsafe { &mut *core::ptr::addr_of_mut!(BUF) };
+    let entry = BatchEntry::new(SYSCALL_GETRANDOM, [buf.as_mut_ptr() as usize, CHUNK, 0]);
+    let mut entries = [entry; BATCH_MAX];
+    if batch(&mut entries[..1]) == -ENOSYS || getrusage(RUSAGE_SELF, &mut RUsage::default()) < 0 {
+        println!("batch or getrusage not supported, skipped");
+        return 0;
+    }
+    // one long syscall, only the kernel can preempt it in the middle
+    let mut elapsed = 0;
+    let mut switches = 0;
+    for _ in 0..16 {
+        let before = involuntary_switches();
+        let start = get_time();
+        assert_eq!(batch(&mut entries), BATCH_MAX as isize);
+        elapsed = get_time() - start;
+        switches = involuntary_switches() - before;
+        if elapsed >= MIN_BATCH_MS {
+            break;
+        }
+    }
+    println!("a {}ms syscall was preempted {} times", elapsed, switches);
+    if elapsed < MIN_BATCH_MS {
+        println!("batch too fast to measure, skipped");
+        return 0;
+    }
+    // without preemption points the task switches only once the syscall
+    // returns, at most once
+    assert!(switches >= 2);
+    assert!(entries.iter().all(|e| e.ret == CHUNK as isize));
+    println!("Test resched OK!");
+    0
+}
@@ -0,0 +1,32 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use syscall_abi::VDSO;
+use user_lib::{write, EFAULT};
+
+const STDOUT: usize = 1;
+/// first address above the user half of SV39
+const USER_END: usize = 1 << 38;
+
+#[no_mangle]
+fn main() -> i32 {
+    let text = main as usize;
+    // addresses that only alias mapped pages once truncated to 39 bits
+    for ptr in [text | (1 << 39), text | (1 << 63), text + USER_END] {
+        let buf = unsafe { core::slice::from_raw_parts(ptr as *const u8, 16) };
+        assert_eq!(write(STDOUT, buf), -EFAULT);
+    }
+    // a buffer is never followed across the gap between the halves
+    let last = unsafe { core::slice::from_raw_parts((USER_END - 8) as *const u8, 16) };
+    assert_eq!(write(STDOUT, last), -EFAULT);
+    // pages mapped for user space in the upper half are valid buffers, the
+    // vdso page is zero after its first fields
+    let vdso = unsafe { core::slice::from_raw_parts((VDSO + 16) as *const u8, 16) };
+    assert_eq!(write(STDOUT, vdso), 16);
+    println!("");
+    println!("Test write_alias OK!");
+    0
+}
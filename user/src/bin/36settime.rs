@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clock_gettime, clock_settime, get_time, TimeSpec, CLOCK_REALTIME, EINVAL};
+
+/// more than the nanoseconds a `u64` holds
+const TOO_LATE: usize = 1 << 40;
+/// nanoseconds fit in a `u64` but not in the signed wall clock
+const PAST_2262: usize = 10_000_000_000;
+
+fn now() -> TimeSpec {
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_REALTIME, &mut ts), 0);
+    ts
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let before = now();
+    for (sec, nsec) in [
+        (-1isize as usize, 0),
+        (isize::MAX as usize, 0),
+        (TOO_LATE, 0),
+        (PAST_2262, 0),
+        (0, 1_000_000_000),
+    ] {
+        assert_eq!(
+            clock_settime(CLOCK_REALTIME, &TimeSpec { sec, nsec }),
+            -EINVAL
+        );
+    }
+    // rejected times leave the clock alone
+    let after = now();
+    assert!(after.sec >= before.sec && after.sec - before.sec < 10);
+    // setting the clock to the time it shows keeps it running from there
+    assert_eq!(clock_settime(CLOCK_REALTIME, &after), 0);
+    assert!(now().sec >= after.sec);
+    // a time before the uptime reads back as set
+    let start_ms = get_time() as usize;
+    assert_eq!(
+        clock_settime(CLOCK_REALTIME, &TimeSpec { sec: 1, nsec: 0 }),
+        0
+    );
+    let early = now();
+    assert!(early.sec >= 1 && early.sec < 10);
+    // put the clock back to where it would have been
+    let ns = after.nsec + (get_time() as usize - start_ms) * 1_000_000;
+    let restored = TimeSpec {
+        sec: after.sec + ns / 1_000_000_000,
+        nsec: ns % 1_000_000_000,
+    };
+    assert_eq!(clock_settime(CLOCK_REALTIME, &restored), 0);
+    println!("Test settime OK!");
+    0
+}
@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    clock_getres, clock_gettime, TimeSpec, CLOCK_MONOTONIC, CLOCK_PROCESS_CPUTIME_ID,
+    CLOCK_REALTIME, CLOCK_THREAD_CPUTIME_ID, EINVAL,
+};
+
+fn monotonic_ns() -> usize {
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC, &mut ts), 0);
+    ts.sec * 1_000_000_000 + ts.nsec
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let mut res = TimeSpec::default();
+    for clock_id in [
+        CLOCK_REALTIME,
+        CLOCK_MONOTONIC,
+        CLOCK_PROCESS_CPUTIME_ID,
+        CLOCK_THREAD_CPUTIME_ID,
+    ] {
+        assert_eq!(clock_getres(clock_id, &mut res), 0);
+        // finer than a timer tick
+        assert!(res.sec == 0 && res.nsec > 0 && res.nsec < 10_000_000);
+    }
+    let mut unused = TimeSpec::default();
+    assert_eq!(clock_getres(4, &mut unused), -EINVAL);
+    // the clock never advances by less than its resolution, give or take
+    // the rounding to nanoseconds
+    let mut steps = 0;
+    let mut last = monotonic_ns();
+    while steps < 100 {
+        let now = monotonic_ns();
+        if now != last {
+            assert!(now - last + 1 >= res.nsec);
+            last = now;
+            steps += 1;
+        }
+    }
+    println!("clock resolution is {}ns", res.nsec);
+    println!("Test clock_getres OK!");
+    0
+}
@@ -1,19 +1,69 @@
-use super::write;
+use super::{batch, write, BatchEntry, ENOSYS};
 use core::fmt::{self, Write};
-
-struct Stdout;
+use syscall_abi::SYSCALL_WRITE;
 
 const STDOUT: usize = 1;
+/// bytes of a `print` buffered before they are written
+const BUF_SIZE: usize = 512;
+/// pieces of a `print` buffered before they are written
+const MAX_PIECES: usize = 16;
+
+/// Output of one `print`. Each piece written by the formatter becomes a
+/// `write` entry of a batch, so a `print` of several values still takes a
+/// single trap.
+struct Stdout {
+    buf: [u8; BUF_SIZE],
+    len: usize,
+    entries: [BatchEntry; MAX_PIECES],
+    count: usize,
+}
+
+impl Stdout {
+    fn new() -> Self {
+        Self {
+            buf: [0; BUF_SIZE],
+            len: 0,
+            entries: [BatchEntry::default(); MAX_PIECES],
+            count: 0,
+        }
+    }
+    /// write the buffered pieces in order
+    fn flush(&mut self) {
+        let entries = &mut self.entries[..self.count];
+        if batch(entries) == -ENOSYS {
+            for entry in entries.iter() {
+                let [_, ptr, len] = entry.args;
+                write(STDOUT, unsafe {
+                    core::slice::from_raw_parts(ptr as *const u8, len)
+                });
+            }
+        }
+        self.len = 0;
+        self.count = 0;
+    }
+}
 
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        write(STDOUT, s.as_bytes());
+        for piece in s.as_bytes().chunks(BUF_SIZE) {
+            if self.len + piece.len() > BUF_SIZE || self.count == MAX_PIECES {
+                self.flush();
+            }
+            let buf = &mut self.buf[self.len..self.len + piece.len()];
+            buf.copy_from_slice(piece);
+            self.entries[self.count] =
+                BatchEntry::new(SYSCALL_WRITE, [STDOUT, buf.as_ptr() as usize, buf.len()]);
+            self.len += piece.len();
+            self.count += 1;
+        }
         Ok(())
     }
 }
 
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    let mut stdout = Stdout::new();
+    stdout.write_fmt(args).unwrap();
+    stdout.flush();
 }
 
 #[macro_export]
@@ -0,0 +1,52 @@
+//! Conversion of seconds since the Unix epoch to UTC calendar dates
+
+use core::fmt::{self, Display, Formatter};
+
+const SECS_PER_DAY: usize = 86400;
+
+/// a UTC date and time
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: usize,
+    pub month: usize,
+    pub day: usize,
+    pub hour: usize,
+    pub minute: usize,
+    pub second: usize,
+}
+
+impl DateTime {
+    /// convert seconds since 1970-01-01T00:00:00Z
+    pub fn from_epoch_secs(secs: usize) -> Self {
+        let days = secs / SECS_PER_DAY;
+        let rem = secs % SECS_PER_DAY;
+        // days to civil date, counting in 400-year eras starting at 0000-03-01
+        let z = days + 719468;
+        let era = z / 146097;
+        let doe = z - era * 146097;
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + usize::from(month <= 2);
+        Self {
+            year,
+            month,
+            day,
+            hour: rem / 3600,
+            minute: rem % 3600 / 60,
+            second: rem % 60,
+        }
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
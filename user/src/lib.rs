@@ -4,7 +4,10 @@
 
 #[macro_use]
 pub mod console;
+pub mod ansi;
+pub mod date;
 mod lang_items;
+pub mod sync;
 mod syscall;
 
 #[no_mangle]
@@ -31,7 +34,83 @@ fn clear_bss() {
     });
 }
 
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use syscall::*;
+use syscall_abi::{ProcInfo, VdsoData, PROC_INFO, PROC_INFO_MAGIC, VDSO};
+
+/// Operation not permitted
+pub const EPERM: isize = 1;
+/// No such process
+pub const ESRCH: isize = 3;
+/// Bad file descriptor
+pub const EBADF: isize = 9;
+/// Out of memory
+pub const ENOMEM: isize = 12;
+/// Bad address
+pub const EFAULT: isize = 14;
+/// Invalid argument
+pub const EINVAL: isize = 22;
+/// Function not implemented
+pub const ENOSYS: isize = 38;
+
+/// optional kernel features, see [`kernel_features`]
+pub use syscall_abi::{
+    FEATURE_BATCH, FEATURE_FAULT_INJECT, FEATURE_FRAME_DEBUG, FEATURE_GETRANDOM,
+    FEATURE_HW_COUNTERS, FEATURE_IO_STATS, FEATURE_LOADAVG, FEATURE_MEMORY_MAP, FEATURE_MEMWATCH,
+    FEATURE_PROC_INFO, FEATURE_RUSAGE, FEATURE_SHUTDOWN, FEATURE_STRACE, FEATURE_SYSINFO,
+    FEATURE_THREAD_CPUTIME, FEATURE_UNAME, FEATURE_VDSO,
+};
+
+/// set in [`KERNEL_FEATURES`] once the kernel has been probed
+const FEATURES_PROBED: usize = 1 << (usize::BITS - 1);
+static KERNEL_FEATURES: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the bitmap of optional features supported by the kernel.
+///
+/// The kernel is asked only once; a kernel without the probing syscall is
+/// assumed to support no optional feature.
+pub fn kernel_features() -> usize {
+    let features = KERNEL_FEATURES.load(Ordering::Relaxed);
+    if features & FEATURES_PROBED != 0 {
+        return features & !FEATURES_PROBED;
+    }
+    let features = sys_kernel_features().max(0) as usize;
+    KERNEL_FEATURES.store(features | FEATURES_PROBED, Ordering::Relaxed);
+    features
+}
+
+fn has_feature(feature: usize) -> bool {
+    kernel_features() & feature != 0
+}
+
+/// invoke a syscall by number, e.g. to probe a kernel for it
+pub fn raw_syscall(id: usize, args: [usize; 3]) -> isize {
+    syscall(id, args)
+}
+
+/// terminal window size, see [`get_winsize`]
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct WinSize {
+    pub rows: u16,
+    pub cols: u16,
+    pub xpixel: u16,
+    pub ypixel: u16,
+}
+
+const TIOCGWINSZ: usize = 0x5413;
+const TIOCSWINSZ: usize = 0x5414;
+
+/// get the window size of the terminal behind `fd`
+pub fn get_winsize(fd: usize, winsize: &mut WinSize) -> isize {
+    sys_ioctl(fd, TIOCGWINSZ, winsize as *mut _ as usize)
+}
+
+/// set the window size of the terminal behind `fd`
+pub fn set_winsize(fd: usize, winsize: &WinSize) -> isize {
+    sys_ioctl(fd, TIOCSWINSZ, winsize as *const _ as usize)
+}
 
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf)
@@ -48,3 +127,313 @@ pub fn yield_() -> isize {
 pub fn get_time() -> isize {
     sys_get_time()
 }
+
+/// Read a field of the process information page, retrying while the kernel
+/// updates it. Returns `None` if the kernel has no such page.
+fn read_proc_info(field: impl Fn(&ProcInfo) -> usize) -> Option<usize> {
+    if !has_feature(FEATURE_PROC_INFO) {
+        return None;
+    }
+    let info = PROC_INFO as *const ProcInfo;
+    unsafe {
+        if core::ptr::addr_of!((*info).magic).read_volatile() != PROC_INFO_MAGIC {
+            return None;
+        }
+        loop {
+            let seq = core::ptr::addr_of!((*info).seq).read_volatile();
+            let value = field(&*info);
+            if seq % 2 == 0 && core::ptr::addr_of!((*info).seq).read_volatile() == seq {
+                return Some(value);
+            }
+        }
+    }
+}
+
+/// id of this process, read from the process information page without a
+/// syscall if possible
+pub fn getpid() -> isize {
+    match read_proc_info(|info| info.pid) {
+        Some(pid) => pid as isize,
+        None => sys_getpid(),
+    }
+}
+
+/// same as [`getpid`], but always asks the kernel
+pub fn getpid_syscall() -> isize {
+    sys_getpid()
+}
+
+/// maximum number of entries of a [`batch`]
+pub const BATCH_MAX: usize = 64;
+/// stop the batch if this entry fails
+pub const BATCH_STOP_ON_ERROR: usize = 1 << 0;
+
+/// one syscall of a [`batch`]
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct BatchEntry {
+    pub id: usize,
+    pub args: [usize; 3],
+    /// `BATCH_*` flags
+    pub flags: usize,
+    /// filled with the return value of the syscall
+    pub ret: isize,
+}
+
+impl BatchEntry {
+    pub fn new(id: usize, args: [usize; 3]) -> Self {
+        Self {
+            id,
+            args,
+            flags: 0,
+            ret: 0,
+        }
+    }
+}
+
+/// Execute the syscalls in `entries` in order with a single trap, storing
+/// each return value into its entry. Returns the number of entries executed.
+pub fn batch(entries: &mut [BatchEntry]) -> isize {
+    if !has_feature(FEATURE_BATCH) {
+        return -ENOSYS;
+    }
+    sys_batch(entries)
+}
+
+/// power off the machine, or restart it if `reboot`; only returns on error
+pub fn shutdown(reboot: bool) -> isize {
+    if !has_feature(FEATURE_SHUTDOWN) {
+        return -ENOSYS;
+    }
+    sys_shutdown(reboot)
+}
+
+/// enable or disable kernel logging of every syscall of process `pid`
+pub fn strace(pid: usize, enable: bool) -> isize {
+    if !has_feature(FEATURE_STRACE) {
+        return -ENOSYS;
+    }
+    sys_strace(pid, enable as usize)
+}
+
+/// Same as [`get_time`] without entering the kernel: reads the `time`
+/// counter directly and scales it by the frequency published in the vdso
+/// page. Falls back to the syscall if the kernel has no vdso page.
+pub fn get_time_fast() -> isize {
+    if !has_feature(FEATURE_VDSO) {
+        return sys_get_time();
+    }
+    let vdso = unsafe { &*(VDSO as *const VdsoData) };
+    let time: usize;
+    unsafe {
+        asm!("rdtime {}", out(reg) time);
+    }
+    (time / (vdso.clock_freq / 1000)) as isize
+}
+
+pub const CLOCK_REALTIME: usize = 0;
+pub const CLOCK_MONOTONIC: usize = 1;
+pub const CLOCK_PROCESS_CPUTIME_ID: usize = 2;
+pub const CLOCK_THREAD_CPUTIME_ID: usize = 3;
+
+/// time in seconds and nanoseconds
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct TimeSpec {
+    pub sec: usize,
+    pub nsec: usize,
+}
+
+pub fn clock_gettime(clock_id: usize, ts: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clock_id, ts)
+}
+
+/// resolution of clock `clock_id`
+pub fn clock_getres(clock_id: usize, res: &mut TimeSpec) -> isize {
+    sys_clock_getres(clock_id, res)
+}
+
+/// CPU time of thread `tid` (0 for the caller) in microseconds
+pub fn thread_cputime(tid: usize) -> isize {
+    if !has_feature(FEATURE_THREAD_CPUTIME) {
+        return -ENOSYS;
+    }
+    sys_thread_cputime(tid)
+}
+
+/// set the wall-clock time, only `CLOCK_REALTIME` can be set
+pub fn clock_settime(clock_id: usize, ts: &TimeSpec) -> isize {
+    sys_clock_settime(clock_id, ts)
+}
+
+/// map area information filled by [`get_memory_map`]
+pub use syscall_abi::{AreaInfo, AREA_PERM_R, AREA_PERM_U, AREA_PERM_W, AREA_PERM_X};
+
+/// fill `areas` with the map areas of the current process and return the
+/// total number of areas, which may exceed `areas.len()`
+pub fn get_memory_map(areas: &mut [AreaInfo]) -> isize {
+    if !has_feature(FEATURE_MEMORY_MAP) {
+        return -ENOSYS;
+    }
+    sys_get_memory_map(areas)
+}
+
+/// Enable or disable the periodic scans of which pages this process
+/// accesses, reported in `touched` and `hot` of [`get_memory_map`].
+pub fn memwatch(enable: bool) -> isize {
+    if !has_feature(FEATURE_MEMWATCH) {
+        return -ENOSYS;
+    }
+    sys_memwatch(enable)
+}
+
+/// load information filled by [`loadavg`]
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct LoadAvg {
+    /// load averages over 1s, 10s and 60s, scaled by 1000
+    pub loads: [u64; 3],
+    pub processes: u64,
+    pub threads: u64,
+}
+
+pub fn loadavg(load_avg: &mut LoadAvg) -> isize {
+    if !has_feature(FEATURE_LOADAVG) {
+        return -ENOSYS;
+    }
+    sys_loadavg(load_avg)
+}
+
+/// fault injection sites of [`fault_inject`]
+pub const FAULT_FRAME_ALLOC: usize = 0;
+pub const FAULT_HEAP_ALLOC: usize = 1;
+/// fail the next `count` operations
+pub const FAULT_MODE_NEXT: usize = 0;
+/// fail every `count`-th operation
+pub const FAULT_MODE_EVERY_NTH: usize = 1;
+
+/// Make operations at `site` fail for syscalls of this process, see
+/// `FAULT_MODE_*`. A `count` of 0 stops the injection. Needs a kernel built
+/// with the `fault_inject` feature.
+pub fn fault_inject(site: usize, mode: usize, count: usize) -> isize {
+    if !has_feature(FEATURE_FAULT_INJECT) {
+        return -ENOSYS;
+    }
+    sys_fault_inject(site, mode, count)
+}
+
+/// system information filled by [`sysinfo`]
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct SysInfo {
+    /// milliseconds since boot
+    pub uptime_ms: usize,
+    /// bytes of physical memory for frames
+    pub total_ram: usize,
+    pub free_ram: usize,
+    /// bytes managed by and allocated from the kernel heap
+    pub heap_total: usize,
+    pub heap_used: usize,
+    /// bytes of frames the kernel heap grew by
+    pub heap_grown: usize,
+    /// 1 if the kernel programs the timer through `stimecmp`
+    pub timer_sstc: usize,
+}
+
+pub fn sysinfo(info: &mut SysInfo) -> isize {
+    if !has_feature(FEATURE_SYSINFO) {
+        return -ENOSYS;
+    }
+    sys_sysinfo(info)
+}
+
+/// I/O accounting filled by [`get_io_stats`]
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct IoStats {
+    /// bytes written to the console
+    pub console_write_bytes: u64,
+    /// syscalls made, including the ones of a batch
+    pub syscalls: u64,
+}
+
+/// get I/O accounting of process `pid`, which must be the caller
+pub fn get_io_stats(pid: usize, stats: &mut IoStats) -> isize {
+    if !has_feature(FEATURE_IO_STATS) {
+        return -ENOSYS;
+    }
+    sys_get_io_stats(pid, stats)
+}
+
+pub use syscall_abi::{uts_str, Utsname};
+
+/// get the name, version and build information of the kernel
+pub fn uname(buf: &mut Utsname) -> isize {
+    if !has_feature(FEATURE_UNAME) {
+        return -ENOSYS;
+    }
+    sys_uname(buf)
+}
+
+pub const RUSAGE_SELF: isize = 0;
+pub const RUSAGE_CHILDREN: isize = -1;
+
+/// resource usage filled by [`getrusage`]
+#[repr(C)]
+#[derive(Copy, Clone, Default, Debug)]
+pub struct RUsage {
+    /// CPU time in microseconds, including syscalls
+    pub cpu_time_us: u64,
+    /// peak resident set size in KiB
+    pub maxrss_kb: u64,
+    /// page faults resolved without I/O
+    pub minflt: u64,
+    /// page faults which needed I/O
+    pub majflt: u64,
+    /// voluntary context switches
+    pub nvcsw: u64,
+    /// involuntary context switches
+    pub nivcsw: u64,
+}
+
+/// get resource usage of the caller (`RUSAGE_SELF`) or its children
+/// (`RUSAGE_CHILDREN`)
+pub fn getrusage(who: isize, usage: &mut RUsage) -> isize {
+    if !has_feature(FEATURE_RUSAGE) {
+        return -ENOSYS;
+    }
+    sys_getrusage(who, usage)
+}
+
+pub fn getrandom(buf: &mut [u8]) -> isize {
+    if !has_feature(FEATURE_GETRANDOM) {
+        return -ENOSYS;
+    }
+    sys_getrandom(buf)
+}
+
+pub fn rand_u64() -> u64 {
+    let mut buf = [0u8; 8];
+    getrandom(&mut buf);
+    u64::from_le_bytes(buf)
+}
+
+/// read the `cycle` counter, only if the kernel reports
+/// [`FEATURE_HW_COUNTERS`]
+pub fn cycles() -> u64 {
+    let cycles: u64;
+    unsafe {
+        asm!("rdcycle {}", out(reg) cycles);
+    }
+    cycles
+}
+
+/// read the `instret` counter, only if the kernel reports
+/// [`FEATURE_HW_COUNTERS`]
+pub fn instret() -> u64 {
+    let instret: u64;
+    unsafe {
+        asm!("rdinstret {}", out(reg) instret);
+    }
+    instret
+}
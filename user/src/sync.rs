@@ -0,0 +1,66 @@
+//! Spin-wait helpers for user-space synchronization
+
+use super::yield_;
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// spins of [`backoff`] double up to `1 << BACKOFF_SPIN_LIMIT`, after which
+/// it yields the CPU instead
+const BACKOFF_SPIN_LIMIT: u32 = 10;
+
+/// Tell the CPU we are spinning.
+///
+/// Emits `pause` from Zihintpause. Its encoding is a `fence w, 0`, so CPUs
+/// without the extension run it as a harmless fence instead of trapping.
+#[inline(always)]
+pub fn spin_loop_hint() {
+    unsafe {
+        asm!(".insn i 0x0f, 0, x0, x0, 0x010");
+    }
+}
+
+/// Wait after the `iteration`-th failed attempt: spin exponentially longer,
+/// then give up the CPU once spinning is unlikely to help.
+pub fn backoff(iteration: u32) {
+    if iteration < BACKOFF_SPIN_LIMIT {
+        for _ in 0..1 << iteration {
+            spin_loop_hint();
+        }
+    } else {
+        yield_();
+    }
+}
+
+/// barrier for `n` participants which spin with [`backoff`]
+pub struct SpinBarrier {
+    n: usize,
+    /// participants arrived in the current round
+    count: AtomicUsize,
+    /// number of completed rounds
+    generation: AtomicUsize,
+}
+
+impl SpinBarrier {
+    pub const fn new(n: usize) -> Self {
+        Self {
+            n,
+            count: AtomicUsize::new(0),
+            generation: AtomicUsize::new(0),
+        }
+    }
+    /// Wait until all `n` participants arrived, returns true in the last one.
+    pub fn wait(&self) -> bool {
+        let generation = self.generation.load(Ordering::Acquire);
+        if self.count.fetch_add(1, Ordering::AcqRel) + 1 == self.n {
+            self.count.store(0, Ordering::Relaxed);
+            self.generation.fetch_add(1, Ordering::Release);
+            return true;
+        }
+        let mut iteration = 0;
+        while self.generation.load(Ordering::Acquire) == generation {
+            backoff(iteration);
+            iteration += 1;
+        }
+        false
+    }
+}
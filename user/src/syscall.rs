@@ -1,11 +1,16 @@
+use super::{AreaInfo, BatchEntry, IoStats, LoadAvg, RUsage, SysInfo, TimeSpec, Utsname};
 use core::arch::asm;
+use syscall_abi::{
+    SYSCALL_BATCH, SYSCALL_CLOCK_GETRES, SYSCALL_CLOCK_GETTIME, SYSCALL_CLOCK_SETTIME,
+    SYSCALL_EXIT, SYSCALL_FAULT_INJECT, SYSCALL_GETPID, SYSCALL_GETRANDOM, SYSCALL_GETRUSAGE,
+    SYSCALL_GET_IO_STATS, SYSCALL_GET_MEMORY_MAP, SYSCALL_GET_TIME, SYSCALL_IOCTL,
+    SYSCALL_KERNEL_FEATURES, SYSCALL_LOADAVG, SYSCALL_MEMWATCH, SYSCALL_SHUTDOWN, SYSCALL_STRACE,
+    SYSCALL_SYSINFO, SYSCALL_THREAD_CPUTIME, SYSCALL_UNAME, SYSCALL_WRITE, SYSCALL_YIELD,
+};
 
-const SYSCALL_WRITE: usize = 64;
-const SYSCALL_EXIT: usize = 93;
-const SYSCALL_YIELD: usize = 124;
-const SYSCALL_GET_TIME: usize = 169;
-
-fn syscall(id: usize, args: [usize; 3]) -> isize {
+/// Make syscall `id`. The unused argument registers are cleared, since the
+/// kernel warns about syscalls with garbage in them.
+pub fn syscall(id: usize, args: [usize; 3]) -> isize {
     let mut ret: isize;
     unsafe {
         asm!(
@@ -13,12 +18,19 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
             inlateout("x10") args[0] => ret,
             in("x11") args[1],
             in("x12") args[2],
+            in("x13") 0,
+            in("x14") 0,
+            in("x15") 0,
             in("x17") id
         );
     }
     ret
 }
 
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    syscall(SYSCALL_IOCTL, [fd, request, arg])
+}
+
 pub fn sys_write(fd: usize, buffer: &[u8]) -> isize {
     syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
 }
@@ -34,3 +46,90 @@ pub fn sys_yield() -> isize {
 pub fn sys_get_time() -> isize {
     syscall(SYSCALL_GET_TIME, [0, 0, 0])
 }
+
+pub fn sys_getpid() -> isize {
+    syscall(SYSCALL_GETPID, [0, 0, 0])
+}
+
+pub fn sys_clock_gettime(clock_id: usize, ts: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME, [clock_id, ts as *mut _ as usize, 0])
+}
+
+pub fn sys_clock_getres(clock_id: usize, res: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETRES, [clock_id, res as *mut _ as usize, 0])
+}
+
+pub fn sys_clock_settime(clock_id: usize, ts: &TimeSpec) -> isize {
+    syscall(
+        SYSCALL_CLOCK_SETTIME,
+        [clock_id, ts as *const _ as usize, 0],
+    )
+}
+
+pub fn sys_getrandom(buffer: &mut [u8]) -> isize {
+    syscall(
+        SYSCALL_GETRANDOM,
+        [buffer.as_mut_ptr() as usize, buffer.len(), 0],
+    )
+}
+
+pub fn sys_get_memory_map(areas: &mut [AreaInfo]) -> isize {
+    syscall(
+        SYSCALL_GET_MEMORY_MAP,
+        [areas.as_mut_ptr() as usize, areas.len(), 0],
+    )
+}
+
+pub fn sys_loadavg(load_avg: &mut LoadAvg) -> isize {
+    syscall(SYSCALL_LOADAVG, [load_avg as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_kernel_features() -> isize {
+    syscall(SYSCALL_KERNEL_FEATURES, [0, 0, 0])
+}
+
+pub fn sys_strace(pid: usize, enable: usize) -> isize {
+    syscall(SYSCALL_STRACE, [pid, enable, 0])
+}
+
+pub fn sys_shutdown(reboot: bool) -> isize {
+    syscall(SYSCALL_SHUTDOWN, [reboot as usize, 0, 0])
+}
+
+pub fn sys_batch(entries: &mut [BatchEntry]) -> isize {
+    syscall(
+        SYSCALL_BATCH,
+        [entries.as_mut_ptr() as usize, entries.len(), 0],
+    )
+}
+
+pub fn sys_sysinfo(info: &mut SysInfo) -> isize {
+    syscall(SYSCALL_SYSINFO, [info as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_fault_inject(site: usize, mode: usize, count: usize) -> isize {
+    syscall(SYSCALL_FAULT_INJECT, [site, mode, count])
+}
+
+pub fn sys_get_io_stats(pid: usize, stats: &mut IoStats) -> isize {
+    syscall(SYSCALL_GET_IO_STATS, [pid, stats as *mut _ as usize, 0])
+}
+
+pub fn sys_memwatch(enable: bool) -> isize {
+    syscall(SYSCALL_MEMWATCH, [enable as usize, 0, 0])
+}
+
+pub fn sys_uname(buf: &mut Utsname) -> isize {
+    syscall(SYSCALL_UNAME, [buf as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_getrusage(who: isize, usage: &mut RUsage) -> isize {
+    syscall(
+        SYSCALL_GETRUSAGE,
+        [who as usize, usage as *mut _ as usize, 0],
+    )
+}
+
+pub fn sys_thread_cputime(tid: usize) -> isize {
+    syscall(SYSCALL_THREAD_CPUTIME, [tid, 0, 0])
+}